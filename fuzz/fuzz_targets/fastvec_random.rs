@@ -25,7 +25,7 @@ fn do_test<const N: usize>(data: &[u8]) -> FastVec<u8, N> {
     let mut bytes = data.iter().copied();
 
     while let Some(op) = bytes.next() {
-        match op % 3 {
+        match op % 7 {
             0 => {
                 v = FastVec::<u8, N>::new();
             }
@@ -37,6 +37,27 @@ fn do_test<const N: usize>(data: &[u8]) -> FastVec<u8, N> {
             2 => {
                 v.pop();
             }
+            3 => {
+                let insert_pos = next_usize!(bytes) % (v.len() + 1);
+                if v.len() < CAP_GROWTH {
+                    v.insert(insert_pos, next_u8!(bytes));
+                }
+            }
+            4 => {
+                if !v.is_empty() {
+                    let remove_pos = next_usize!(bytes) % v.len();
+                    v.remove(remove_pos);
+                }
+            }
+            5 => {
+                if !v.is_empty() {
+                    let remove_pos = next_usize!(bytes) % v.len();
+                    v.swap_remove(remove_pos);
+                }
+            }
+            6 => {
+                v.truncate(next_usize!(bytes));
+            }
             _ => panic!("booo"),
         }
     }
@@ -0,0 +1,41 @@
+#!/usr/bin/bash
+# Checks that FASTVEC_MAX_INLINE_BYTES rejects buffers with an oversized inline
+# storage at build time, and accepts them within the budget
+
+set -ex
+
+ROOT=$(cd "$(dirname "$0")/.." && pwd)
+CRATE=$(mktemp -d)
+trap 'rm -rf "$CRATE"' EXIT
+
+mkdir "$CRATE/src"
+cat > "$CRATE/Cargo.toml" <<EOF
+[package]
+name = "inline-budget"
+version = "0.0.0"
+edition = "2021"
+
+[dependencies]
+fastvec = { path = "$ROOT" }
+
+[workspace]
+EOF
+
+# 64 items of 4 KiB each is a 256 KiB inline storage, which is the kind of
+# buffer that overflows the stack
+cat > "$CRATE/src/main.rs" <<EOF
+fn main() {
+    let buf = fastvec::FastVec::<[u8; 4096], 64>::new();
+    assert!(buf.is_empty());
+}
+EOF
+
+cd "$CRATE"
+
+FASTVEC_MAX_INLINE_BYTES=262144 cargo build
+
+if FASTVEC_MAX_INLINE_BYTES=65536 cargo build 2> build.log; then
+    echo "The oversized inline storage was accepted"
+    exit 1
+fi
+grep -q "The inline storage exceeds FASTVEC_MAX_INLINE_BYTES" build.log
@@ -0,0 +1,237 @@
+#[cfg(feature = "alloc")]
+use alloc::alloc;
+use core::{
+    alloc::Layout,
+    fmt,
+    ptr::{self, NonNull},
+};
+
+/// The error returned by an [`Allocator`] when it cannot satisfy a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllocError;
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Memory allocation failed")
+    }
+}
+
+/// A stable-compatible subset of the unstable `core::alloc::Allocator`, which
+/// provides the heap buffer of a [`crate::FastVec`] once it spills. This
+/// allows the buffers to come from arenas, pools or tracking allocators.
+///
+/// # Safety
+///
+/// The memory blocks returned by the allocator must be valid for the
+/// requested layout and must stay valid until they are deallocated through
+/// the same allocator or any of its clones.
+pub unsafe trait Allocator {
+    /// Allocates a memory block fitting the layout, or returns an error if
+    /// the allocation is not possible
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError>;
+
+    /// Deallocates the memory block at `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// The block must have been allocated by this allocator with `layout`.
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+
+    /// Grows the memory block at `ptr` to fit `new_layout`, keeping its
+    /// contents. On failure the original block is left untouched.
+    ///
+    /// # Safety
+    ///
+    /// The block must have been allocated by this allocator with
+    /// `old_layout`, which must not be larger than `new_layout`.
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        debug_assert!(new_layout.size() >= old_layout.size());
+
+        let new_ptr = self.allocate(new_layout)?;
+        ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr(), old_layout.size());
+        self.deallocate(ptr, old_layout);
+
+        Ok(new_ptr)
+    }
+
+    /// Shrinks the memory block at `ptr` to fit `new_layout`, keeping the
+    /// contents which fit. On failure the original block is left untouched.
+    ///
+    /// # Safety
+    ///
+    /// The block must have been allocated by this allocator with
+    /// `old_layout`, which must not be smaller than `new_layout`.
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        debug_assert!(new_layout.size() <= old_layout.size());
+
+        let new_ptr = self.allocate(new_layout)?;
+        ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr(), new_layout.size());
+        self.deallocate(ptr, old_layout);
+
+        Ok(new_ptr)
+    }
+
+    /// Returns `false` if the allocator can never provide any memory, in which
+    /// case running out of the inline storage is reported as
+    /// [`crate::error::Error::CapacityOverflow`] without trying to allocate.
+    #[inline]
+    fn can_allocate(&self) -> bool {
+        true
+    }
+}
+
+unsafe impl<A> Allocator for &A
+where
+    A: Allocator + ?Sized,
+{
+    #[inline]
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        (**self).allocate(layout)
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        (**self).deallocate(ptr, layout)
+    }
+
+    #[inline]
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        (**self).grow(ptr, old_layout, new_layout)
+    }
+
+    #[inline]
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        (**self).shrink(ptr, old_layout, new_layout)
+    }
+
+    #[inline]
+    fn can_allocate(&self) -> bool {
+        (**self).can_allocate()
+    }
+}
+
+/// The global memory allocator, which is the default allocator of
+/// [`crate::FastVec`]. It forwards the calls to the `#[global_allocator]`.
+///
+/// Without the `alloc` feature there is no global allocator, so every
+/// allocation of a non-zero size fails, and the buffers report running out of
+/// the inline storage as [`crate::error::Error::CapacityOverflow`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Global;
+
+impl Global {
+    // Returns a dangling but well aligned pointer for zero sized blocks
+    #[inline]
+    fn dangling(layout: Layout) -> NonNull<u8> {
+        unsafe { NonNull::new_unchecked(layout.align() as *mut u8) }
+    }
+
+    // Resizes a block in place if possible, the layouts must have the same
+    // alignment and non-zero sizes
+    #[inline]
+    unsafe fn realloc(
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        #[cfg(feature = "alloc")]
+        return NonNull::new(alloc::realloc(ptr.as_ptr(), old_layout, new_layout.size()))
+            .ok_or(AllocError);
+
+        #[cfg(not(feature = "alloc"))]
+        {
+            let _ = (ptr, old_layout, new_layout);
+            Err(AllocError)
+        }
+    }
+}
+
+unsafe impl Allocator for Global {
+    #[inline]
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        if layout.size() == 0 {
+            return Ok(Self::dangling(layout));
+        }
+
+        #[cfg(feature = "alloc")]
+        return NonNull::new(unsafe { alloc::alloc(layout) }).ok_or(AllocError);
+
+        #[cfg(not(feature = "alloc"))]
+        return Err(AllocError);
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        #[cfg(feature = "alloc")]
+        if layout.size() != 0 {
+            alloc::dealloc(ptr.as_ptr(), layout);
+        }
+
+        // NOTE: Nothing could have been allocated with a non-zero size
+        #[cfg(not(feature = "alloc"))]
+        let _ = (ptr, layout);
+    }
+
+    #[inline]
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        debug_assert!(new_layout.size() >= old_layout.size());
+
+        if old_layout.size() == 0 || old_layout.align() != new_layout.align() {
+            let new_ptr = self.allocate(new_layout)?;
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr(), old_layout.size());
+            self.deallocate(ptr, old_layout);
+            return Ok(new_ptr);
+        }
+
+        Self::realloc(ptr, old_layout, new_layout)
+    }
+
+    #[inline]
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        debug_assert!(new_layout.size() <= old_layout.size());
+
+        if new_layout.size() == 0 || old_layout.align() != new_layout.align() {
+            let new_ptr = self.allocate(new_layout)?;
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr(), new_layout.size());
+            self.deallocate(ptr, old_layout);
+            return Ok(new_ptr);
+        }
+
+        Self::realloc(ptr, old_layout, new_layout)
+    }
+
+    #[inline]
+    fn can_allocate(&self) -> bool {
+        cfg!(feature = "alloc")
+    }
+}
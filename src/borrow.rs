@@ -0,0 +1,45 @@
+use core::borrow::{Borrow, BorrowMut};
+
+use crate::{allocator::Allocator, growth::GrowthPolicy, FastVec};
+
+impl<T, const N: usize, G, A> AsRef<[T]> for FastVec<T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    fn as_ref(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T, const N: usize, G, A> AsMut<[T]> for FastVec<T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    fn as_mut(&mut self) -> &mut [T] {
+        self
+    }
+}
+
+// NOTE: The Hash, Eq and Ord implementations agree with the slice, which is
+// required for looking up buffers by slice in collections
+impl<T, const N: usize, G, A> Borrow<[T]> for FastVec<T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    fn borrow(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T, const N: usize, G, A> BorrowMut<[T]> for FastVec<T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    fn borrow_mut(&mut self) -> &mut [T] {
+        self
+    }
+}
@@ -0,0 +1,29 @@
+use crate::{allocator::Allocator, growth::GrowthPolicy, FastVec};
+
+impl<T, const N: usize, G, A> Clone for FastVec<T, N, G, A>
+where
+    T: Clone,
+    G: GrowthPolicy,
+    A: Allocator + Clone,
+{
+    /// Clones the items into a new buffer using a clone of the allocator. The
+    /// clone is stored inline if the items fit, even if this buffer spilled.
+    fn clone(&self) -> Self {
+        let mut vec = FastVec::with_capacity_in(self.len(), self.allocator().clone());
+        vec.extend_from_slice(self);
+        vec
+    }
+
+    /// Clones the items of `source` into this buffer, reusing its storage
+    /// (including a heap buffer) and its existing items where possible. The
+    /// allocator is kept.
+    fn clone_from(&mut self, source: &Self) {
+        self.truncate(source.len());
+
+        // NOTE: If a clone panics, the buffer holds the items processed so
+        // far, which are all valid
+        let (init, tail) = source.split_at(self.len());
+        self.clone_from_slice(init);
+        self.extend_from_slice(tail);
+    }
+}
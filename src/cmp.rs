@@ -0,0 +1,158 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+use crate::{allocator::Allocator, growth::GrowthPolicy, FastVec};
+
+// NOTE: All comparisons follow the slice semantics, so the inline capacity,
+// the growth policy, the allocator and the storage state don't matter
+
+impl<T, U, const N: usize, const M: usize, G1, G2, A1, A2> PartialEq<FastVec<U, M, G2, A2>>
+    for FastVec<T, N, G1, A1>
+where
+    T: PartialEq<U>,
+    G1: GrowthPolicy,
+    G2: GrowthPolicy,
+    A1: Allocator,
+    A2: Allocator,
+{
+    #[inline]
+    fn eq(&self, other: &FastVec<U, M, G2, A2>) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl<T, U, const N: usize, G, A> PartialEq<[U]> for FastVec<T, N, G, A>
+where
+    T: PartialEq<U>,
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    #[inline]
+    fn eq(&self, other: &[U]) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl<'a, T, U, const N: usize, G, A> PartialEq<&'a [U]> for FastVec<T, N, G, A>
+where
+    T: PartialEq<U>,
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    #[inline]
+    fn eq(&self, other: &&'a [U]) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl<T, U, const N: usize, const K: usize, G, A> PartialEq<[U; K]> for FastVec<T, N, G, A>
+where
+    T: PartialEq<U>,
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    #[inline]
+    fn eq(&self, other: &[U; K]) -> bool {
+        self[..] == other[..]
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T, U, const N: usize, G, A> PartialEq<Vec<U>> for FastVec<T, N, G, A>
+where
+    T: PartialEq<U>,
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    #[inline]
+    fn eq(&self, other: &Vec<U>) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl<T, U, const N: usize, G, A> PartialEq<FastVec<U, N, G, A>> for [T]
+where
+    T: PartialEq<U>,
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    #[inline]
+    fn eq(&self, other: &FastVec<U, N, G, A>) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl<T, U, const N: usize, G, A> PartialEq<FastVec<U, N, G, A>> for &[T]
+where
+    T: PartialEq<U>,
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    #[inline]
+    fn eq(&self, other: &FastVec<U, N, G, A>) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl<T, U, const N: usize, const K: usize, G, A> PartialEq<FastVec<U, N, G, A>> for [T; K]
+where
+    T: PartialEq<U>,
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    #[inline]
+    fn eq(&self, other: &FastVec<U, N, G, A>) -> bool {
+        self[..] == other[..]
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T, U, const N: usize, G, A> PartialEq<FastVec<U, N, G, A>> for Vec<T>
+where
+    T: PartialEq<U>,
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    #[inline]
+    fn eq(&self, other: &FastVec<U, N, G, A>) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl<T, const N: usize, G, A> Eq for FastVec<T, N, G, A>
+where
+    T: Eq,
+    G: GrowthPolicy,
+    A: Allocator,
+{
+}
+
+impl<T, const N: usize, const M: usize, G1, G2, A1, A2> PartialOrd<FastVec<T, M, G2, A2>>
+    for FastVec<T, N, G1, A1>
+where
+    T: PartialOrd,
+    G1: GrowthPolicy,
+    G2: GrowthPolicy,
+    A1: Allocator,
+    A2: Allocator,
+{
+    /// Compares the items lexicographically, just like slices
+    #[inline]
+    fn partial_cmp(&self, other: &FastVec<T, M, G2, A2>) -> Option<Ordering> {
+        self[..].partial_cmp(&other[..])
+    }
+}
+
+impl<T, const N: usize, G, A> Ord for FastVec<T, N, G, A>
+where
+    T: Ord,
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    /// Compares the items lexicographically, just like slices
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self[..].cmp(&other[..])
+    }
+}
@@ -0,0 +1,112 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use core::mem::{self, ManuallyDrop};
+#[cfg(feature = "alloc")]
+use core::ptr;
+
+use crate::{allocator::Allocator, growth::GrowthPolicy, FastVec};
+
+// NOTE: The heap buffer of a Vec is allocated by the global allocator with
+// the layout of its items, just like the raw parts of a buffer using the
+// Global allocator, so the allocations are handed over without copying
+
+#[cfg(feature = "alloc")]
+impl<T, const N: usize, G> From<Vec<T>> for FastVec<T, N, G>
+where
+    G: GrowthPolicy,
+{
+    /// Adopts the heap buffer of the vector without copying the items if its
+    /// capacity is larger than `N`, otherwise moves the items inline.
+    fn from(vec: Vec<T>) -> Self {
+        let mut vec = ManuallyDrop::new(vec);
+        let (ptr, len, cap) = (vec.as_mut_ptr(), vec.len(), vec.capacity());
+
+        if mem::size_of::<T>() != 0 && cap > N {
+            return unsafe { FastVec::from_raw_parts(ptr, len, cap) };
+        }
+
+        let mut fastvec = FastVec::with_capacity(len);
+        unsafe {
+            let (dst, dst_len, _) = fastvec.ptr_mut();
+            ptr::copy_nonoverlapping(ptr, dst, len);
+            *dst_len = len;
+
+            // NOTE: Frees the buffer of the vector without the moved items
+            vec.set_len(0);
+            ManuallyDrop::drop(&mut vec);
+        }
+        fastvec.check_invariants();
+        fastvec
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T, const N: usize, G> From<FastVec<T, N, G>> for Vec<T>
+where
+    G: GrowthPolicy,
+{
+    /// Hands over the heap buffer without copying the items if the buffer
+    /// spilled, otherwise moves the items to a new vector.
+    fn from(fastvec: FastVec<T, N, G>) -> Self {
+        let mut fastvec = match fastvec.into_raw_parts() {
+            Ok((ptr, len, cap)) => unsafe {
+                return Vec::from_raw_parts(ptr, len, cap);
+            },
+            Err(fastvec) => fastvec,
+        };
+
+        let mut vec = Vec::with_capacity(fastvec.len());
+        unsafe {
+            let (src, len, _) = fastvec.ptr_mut();
+            ptr::copy_nonoverlapping(src, vec.as_mut_ptr(), *len);
+            vec.set_len(*len);
+
+            // NOTE: The items are moved out, so only the storage is freed
+            *len = 0;
+        }
+        vec
+    }
+}
+
+impl<T, const N: usize, const M: usize, G, A> From<[T; M]> for FastVec<T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator + Default,
+{
+    /// Moves the items of the array into the buffer, which stores them inline
+    /// if they fit
+    fn from(array: [T; M]) -> Self {
+        let mut vec = FastVec::with_capacity_in(M, A::default());
+        vec.extend(array);
+        vec
+    }
+}
+
+impl<T, const N: usize, G, A> From<&[T]> for FastVec<T, N, G, A>
+where
+    T: Clone,
+    G: GrowthPolicy,
+    A: Allocator + Default,
+{
+    /// Clones the items of the slice into the buffer, which stores them
+    /// inline if they fit
+    fn from(slice: &[T]) -> Self {
+        let mut vec = FastVec::with_capacity_in(slice.len(), A::default());
+        vec.extend_from_slice(slice);
+        vec
+    }
+}
+
+impl<T, const N: usize, G, A> From<&mut [T]> for FastVec<T, N, G, A>
+where
+    T: Clone,
+    G: GrowthPolicy,
+    A: Allocator + Default,
+{
+    /// Clones the items of the slice into the buffer, which stores them
+    /// inline if they fit
+    fn from(slice: &mut [T]) -> Self {
+        Self::from(&*slice)
+    }
+}
@@ -0,0 +1,15 @@
+use core::fmt;
+
+use crate::{allocator::Allocator, growth::GrowthPolicy, FastVec};
+
+impl<T, const N: usize, G, A> fmt::Debug for FastVec<T, N, G, A>
+where
+    T: fmt::Debug,
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    /// Formats the items as a list, just like slices
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
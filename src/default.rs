@@ -0,0 +1,13 @@
+use crate::{allocator::Allocator, growth::GrowthPolicy, FastVec};
+
+impl<T, const N: usize, G, A> Default for FastVec<T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator + Default,
+{
+    /// Creates a new, empty buffer with the default allocator, see
+    /// [`FastVec::new_in`]
+    fn default() -> Self {
+        FastVec::new_in(A::default())
+    }
+}
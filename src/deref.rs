@@ -1,11 +1,11 @@
-use std::{
+use core::{
     ops::{Deref, DerefMut},
     slice,
 };
 
-use crate::FastVec;
+use crate::{allocator::Allocator, growth::GrowthPolicy, FastVec};
 
-impl<T: Unpin, const N: usize> Deref for FastVec<T, N> {
+impl<T, const N: usize, G: GrowthPolicy, A: Allocator> Deref for FastVec<T, N, G, A> {
     type Target = [T];
 
     fn deref(&self) -> &Self::Target {
@@ -16,7 +16,7 @@ impl<T: Unpin, const N: usize> Deref for FastVec<T, N> {
     }
 }
 
-impl<T: Unpin, const N: usize> DerefMut for FastVec<T, N> {
+impl<T, const N: usize, G: GrowthPolicy, A: Allocator> DerefMut for FastVec<T, N, G, A> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         unsafe {
             let (ptr, len, _) = self.ptr_mut();
@@ -0,0 +1,224 @@
+use core::{
+    fmt,
+    iter::FusedIterator,
+    mem,
+    ptr::{self, NonNull},
+    slice,
+};
+
+use crate::{
+    allocator::{Allocator, Global},
+    error::Operation,
+    growth::{Doubling, GrowthPolicy},
+    FastVec,
+};
+
+/// A draining iterator which removes and yields the items in a range. It is
+/// created by [`FastVec::drain`].
+///
+/// The length of the buffer is cut to the start of the range while this
+/// iterator is alive, and the items after the range (the tail) are moved back
+/// in place when it is dropped. Leaking it leaks every item from the start of
+/// the range to the end of the buffer, including the ones not yet yielded,
+/// and leaves the buffer holding only the items before the range.
+pub struct Drain<'a, T, const N: usize, G = Doubling, A = Global>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    // The index of the first item of the tail
+    pub(crate) tail_start: usize,
+    // The number of items in the tail
+    pub(crate) tail_len: usize,
+    // The items in the range which are not yet yielded
+    pub(crate) iter: slice::Iter<'a, T>,
+    pub(crate) vec: NonNull<FastVec<T, N, G, A>>,
+}
+
+impl<'a, T, const N: usize, G, A> Drain<'a, T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    /// Returns the items which are not yet yielded as a slice
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        self.iter.as_slice()
+    }
+
+    // Fills the gap between the end of the buffer and the tail from the
+    // iterator. Returns `true` if the whole gap is filled.
+    pub(crate) unsafe fn fill<I>(&mut self, replace_with: &mut I) -> bool
+    where
+        I: Iterator<Item = T>,
+    {
+        let vec = self.vec.as_mut();
+        let (ptr, len, _) = vec.ptr_mut();
+
+        while *len < self.tail_start {
+            match replace_with.next() {
+                Some(item) => {
+                    ptr::write(ptr.add(*len), item);
+                    *len += 1;
+                }
+                None => return false,
+            }
+        }
+
+        true
+    }
+
+    // Makes room for `additional` items between the end of the buffer and the
+    // tail, moving the items to the heap if needed
+    pub(crate) unsafe fn move_tail(&mut self, additional: usize) {
+        let vec = self.vec.as_mut();
+
+        // NOTE: The tail is first moved right after the last item and becomes
+        // part of the buffer, so the buffer stays valid if reserving panics
+        let tail_len = mem::replace(&mut self.tail_len, 0);
+        let (ptr, len, _) = vec.ptr_mut();
+        let start = *len;
+        ptr::copy(ptr.add(self.tail_start), ptr.add(start), tail_len);
+        *len += tail_len;
+        self.tail_start = start;
+
+        vec.grow_or_abort(additional, false, Operation::Splice);
+
+        let (ptr, len, _) = vec.ptr_mut();
+        *len = start;
+        let new_tail_start = start + additional;
+        ptr::copy(ptr.add(start), ptr.add(new_tail_start), tail_len);
+        self.tail_start = new_tail_start;
+        self.tail_len = tail_len;
+    }
+}
+
+// NOTE: The Drain holds a mutable borrow of the buffer through a raw pointer,
+// so it follows the auto traits of `&mut FastVec`
+unsafe impl<'a, T, const N: usize, G, A> Send for Drain<'a, T, N, G, A>
+where
+    T: Send,
+    G: GrowthPolicy,
+    A: Allocator + Send,
+{
+}
+
+unsafe impl<'a, T, const N: usize, G, A> Sync for Drain<'a, T, N, G, A>
+where
+    T: Sync,
+    G: GrowthPolicy,
+    A: Allocator + Sync,
+{
+}
+
+impl<'a, T, const N: usize, G, A> Iterator for Drain<'a, T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.iter.next().map(|item| unsafe { ptr::read(item) })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, T, const N: usize, G, A> DoubleEndedIterator for Drain<'a, T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<T> {
+        self.iter.next_back().map(|item| unsafe { ptr::read(item) })
+    }
+}
+
+impl<'a, T, const N: usize, G, A> ExactSizeIterator for Drain<'a, T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+}
+
+impl<'a, T, const N: usize, G, A> FusedIterator for Drain<'a, T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+}
+
+impl<'a, T, const N: usize, G, A> fmt::Debug for Drain<'a, T, N, G, A>
+where
+    T: fmt::Debug,
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Drain").field(&self.iter.as_slice()).finish()
+    }
+}
+
+impl<'a, T, const N: usize, G, A> Drop for Drain<'a, T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    fn drop(&mut self) {
+        // Moves the tail back in place, even if dropping an item panics
+        struct DropGuard<'r, 'a, T, const N: usize, G: GrowthPolicy, A: Allocator>(
+            &'r mut Drain<'a, T, N, G, A>,
+        );
+
+        impl<'r, 'a, T, const N: usize, G: GrowthPolicy, A: Allocator> Drop
+            for DropGuard<'r, 'a, T, N, G, A>
+        {
+            fn drop(&mut self) {
+                unsafe {
+                    let vec = self.0.vec.as_mut();
+                    if self.0.tail_len > 0 {
+                        let (ptr, len, _) = vec.ptr_mut();
+                        let start = *len;
+                        let tail = self.0.tail_start;
+                        if tail != start {
+                            ptr::copy(ptr.add(tail), ptr.add(start), self.0.tail_len);
+                        }
+                        *len = start + self.0.tail_len;
+                    }
+                    vec.poison_spare(self.0.tail_start + self.0.tail_len);
+                    vec.check_invariants();
+                }
+            }
+        }
+
+        let iter = mem::replace(&mut self.iter, [].iter());
+        let drop_len = iter.len();
+        let mut vec = self.vec;
+
+        let _guard = DropGuard(self);
+        if drop_len == 0 {
+            return;
+        }
+
+        // NOTE: The slice iterator only hands out shared references, so the
+        // items are dropped through a pointer derived from the buffer instead
+        let drop_ptr = iter.as_slice().as_ptr();
+        unsafe {
+            let (vec_ptr, _, _) = vec.as_mut().ptr_mut();
+            // NOTE: Zero-sized items all live at the same dangling address
+            let drop_offset = if mem::size_of::<T>() == 0 {
+                0
+            } else {
+                drop_ptr.offset_from(vec_ptr) as usize
+            };
+            let to_drop = ptr::slice_from_raw_parts_mut(vec_ptr.add(drop_offset), drop_len);
+            ptr::drop_in_place(to_drop);
+        }
+    }
+}
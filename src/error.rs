@@ -1,31 +1,171 @@
-use std::fmt::Display;
+use core::{
+    alloc::{Layout, LayoutError},
+    fmt::{self, Display},
+};
 
 /// This is the Error type for FastVec which can signal the source of the issue
 /// if something goes wrong.
-/// 
-// TODO(mtolmacs): Allow packaging the source errors
-#[derive(Debug)]
+///
+/// Every variant records the [`Operation`] which failed and the capacity of
+/// the buffer at the time, so failures can be traced back to the call site.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Error {
     /// OS memory allocation was unsuccessful
-    AllocError,
+    AllocError {
+        /// The layout of the requested memory block
+        layout: Layout,
+        /// The operation which requested the memory
+        operation: Operation,
+        /// The capacity of the buffer before the allocation
+        capacity: usize,
+    },
 
-    /// The memory layout requested is incorrect (should not show up at all,
-    /// it indicates an internal error)
-    LayoutError,
+    /// The memory layout requested is incorrect (too large for the address
+    /// space)
+    LayoutError {
+        /// The error returned when calculating the layout
+        source: LayoutError,
+        /// The operation which requested the layout
+        operation: Operation,
+        /// The capacity of the buffer at the time
+        capacity: usize,
+    },
 
     /// The buffer cannot store more items (too numerous)
-    CapacityOverflow,
+    CapacityOverflow {
+        /// The operation which requested the capacity
+        operation: Operation,
+        /// The capacity of the buffer at the time
+        capacity: usize,
+    },
+}
+
+impl Error {
+    /// Returns the kind of the error without the context
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::AllocError { .. } => ErrorKind::AllocError,
+            Error::LayoutError { .. } => ErrorKind::LayoutError,
+            Error::CapacityOverflow { .. } => ErrorKind::CapacityOverflow,
+        }
+    }
+
+    /// Returns the operation which failed
+    pub fn operation(&self) -> Operation {
+        match *self {
+            Error::AllocError { operation, .. }
+            | Error::LayoutError { operation, .. }
+            | Error::CapacityOverflow { operation, .. } => operation,
+        }
+    }
+
+    /// Returns the capacity of the buffer at the time of the error
+    pub fn capacity(&self) -> usize {
+        match *self {
+            Error::AllocError { capacity, .. }
+            | Error::LayoutError { capacity, .. }
+            | Error::CapacityOverflow { capacity, .. } => capacity,
+        }
+    }
+
+    /// Returns the layout of the failed allocation, if an allocation was
+    /// attempted
+    pub fn layout(&self) -> Option<Layout> {
+        match *self {
+            Error::AllocError { layout, .. } => Some(layout),
+            _ => None,
+        }
+    }
 }
 
 // NOTE: Required for implementing std::error:Error
 impl Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::AllocError { layout, .. } => write!(
+                f,
+                "Allocation error of {} bytes (align {})",
+                layout.size(),
+                layout.align()
+            )?,
+            Error::LayoutError { .. } => write!(f, "Memory layout error")?,
+            Error::CapacityOverflow { .. } => write!(f, "Allocation too large")?,
+        }
+
+        write!(
+            f,
+            " during {} at capacity {}",
+            self.operation(),
+            self.capacity()
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
-            Error::AllocError => write!(f, "Allocation error"),
-            Error::LayoutError => write!(f, "Memory layout error"),
-            Error::CapacityOverflow => write!(f, "Allocation too large"),
+            Error::LayoutError { source, .. } => Some(source),
+            _ => None,
         }
     }
 }
 
-impl std::error::Error for Error {}
\ No newline at end of file
+/// The kind of an [`Error`], without the context of the failure
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// OS memory allocation was unsuccessful
+    AllocError,
+
+    /// The memory layout requested is incorrect
+    LayoutError,
+
+    /// The buffer cannot store more items (too numerous)
+    CapacityOverflow,
+}
+
+/// The buffer operation which failed with an [`Error`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Operation {
+    /// Creating a buffer with a given capacity
+    WithCapacity,
+
+    /// Pushing an item to the end of the buffer
+    Push,
+
+    /// Inserting an item into the buffer
+    Insert,
+
+    /// Reserving capacity for additional items
+    Reserve,
+
+    /// Reserving capacity for exactly the additional items
+    ReserveExact,
+
+    /// Extending the buffer with the items of an iterator or slice
+    Extend,
+
+    /// Replacing a range of the buffer with the items of an iterator
+    Splice,
+
+    /// Moving the items to the heap
+    Spill,
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Operation::WithCapacity => "with_capacity",
+            Operation::Push => "push",
+            Operation::Insert => "insert",
+            Operation::Reserve => "reserve",
+            Operation::ReserveExact => "reserve_exact",
+            Operation::Extend => "extend",
+            Operation::Splice => "splice",
+            Operation::Spill => "spill",
+        };
+
+        f.write_str(name)
+    }
+}
@@ -0,0 +1,106 @@
+use core::{iter::FromIterator, ptr};
+
+use crate::{allocator::Allocator, error::Operation, growth::GrowthPolicy, FastVec};
+
+impl<T, const N: usize, G, A> Extend<T> for FastVec<T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.extend_iter(iter.into_iter(), Operation::Extend);
+    }
+}
+
+impl<T, const N: usize, G, A> FastVec<T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    // Appends the items of the iterator, reporting a failure to grow as the
+    // given operation, so splice() can share it with extend()
+    pub(crate) fn extend_iter<I: Iterator<Item = T>>(&mut self, mut iter: I, operation: Operation) {
+        // NOTE: Reserve for the lower bound of the size hint up front, which
+        // decides whether the items fit inline or need a heap allocation
+        let (lower_bound, _) = iter.size_hint();
+        self.grow_or_abort(lower_bound, false, operation);
+
+        'fill: loop {
+            unsafe {
+                let (ptr, len, cap) = self.ptr_mut();
+                let mut guard = SetLenOnDrop::new(len);
+                while guard.local_len < cap {
+                    match iter.next() {
+                        Some(item) => {
+                            ptr::write(ptr.add(guard.local_len), item);
+                            guard.local_len += 1;
+                        }
+                        None => break 'fill,
+                    }
+                }
+            }
+
+            // The size hint was too low, so make room for the rest based on
+            // the size hint of the remaining items
+            match iter.next() {
+                Some(item) => {
+                    let (lower_bound, _) = iter.size_hint();
+                    self.grow_or_abort(lower_bound.saturating_add(1), false, operation);
+                    unsafe {
+                        let (ptr, len, _) = self.ptr_mut();
+                        ptr::write(ptr.add(*len), item);
+                        *len += 1;
+                    }
+                }
+                None => break,
+            }
+        }
+
+        self.check_invariants();
+    }
+}
+
+impl<'a, T, const N: usize, G, A> Extend<&'a T> for FastVec<T, N, G, A>
+where
+    T: Copy + 'a,
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T, const N: usize, G, A> FromIterator<T> for FastVec<T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator + Default,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut vec = FastVec::new_in(A::default());
+        vec.extend(iter);
+        vec
+    }
+}
+
+// Keeps the length in a local variable while items are written in a loop and
+// stores it back on drop, even if the iterator panics
+pub(crate) struct SetLenOnDrop<'a> {
+    len: &'a mut usize,
+    pub(crate) local_len: usize,
+}
+
+impl<'a> SetLenOnDrop<'a> {
+    #[inline]
+    pub(crate) fn new(len: &'a mut usize) -> Self {
+        let local_len = *len;
+        SetLenOnDrop { len, local_len }
+    }
+}
+
+impl<'a> Drop for SetLenOnDrop<'a> {
+    #[inline]
+    fn drop(&mut self) {
+        *self.len = self.local_len;
+    }
+}
@@ -0,0 +1,109 @@
+use core::ptr;
+
+use crate::{
+    allocator::{Allocator, Global},
+    growth::{Doubling, GrowthPolicy},
+    FastVec,
+};
+
+/// An iterator which removes and yields the items matching a predicate. It is
+/// created by [`FastVec::extract_if`].
+///
+/// The length of the buffer is zero while this iterator is alive and gets
+/// restored when it is dropped, so leaking it cannot cause double drops.
+pub struct ExtractIf<'a, T, const N: usize, F, G = Doubling, A = Global>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+    F: FnMut(&mut T) -> bool,
+{
+    vec: &'a mut FastVec<T, N, G, A>,
+    // The index of the next item to inspect
+    idx: usize,
+    // The index after the last item to inspect
+    end: usize,
+    // The number of items extracted so far
+    del: usize,
+    // The length of the buffer before the extraction started
+    old_len: usize,
+    pred: F,
+}
+
+impl<'a, T, const N: usize, F, G, A> ExtractIf<'a, T, N, F, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+    F: FnMut(&mut T) -> bool,
+{
+    pub(crate) fn new(
+        vec: &'a mut FastVec<T, N, G, A>,
+        idx: usize,
+        end: usize,
+        old_len: usize,
+        pred: F,
+    ) -> Self {
+        Self {
+            vec,
+            idx,
+            end,
+            del: 0,
+            old_len,
+            pred,
+        }
+    }
+}
+
+impl<'a, T, const N: usize, F, G, A> Iterator for ExtractIf<'a, T, N, F, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+    F: FnMut(&mut T) -> bool,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        unsafe {
+            let (ptr, _, _) = self.vec.ptr_mut();
+            while self.idx < self.end {
+                let i = self.idx;
+                let cur = ptr.add(i);
+                let extract = (self.pred)(&mut *cur);
+                // NOTE: Bumped after the predicate, so if it panics the
+                // current item is kept by the drop handler
+                self.idx += 1;
+                if extract {
+                    self.del += 1;
+                    return Some(ptr::read(cur));
+                } else if self.del > 0 {
+                    ptr::copy_nonoverlapping(cur, ptr.add(i - self.del), 1);
+                }
+            }
+
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.end - self.idx))
+    }
+}
+
+impl<'a, T, const N: usize, F, G, A> Drop for ExtractIf<'a, T, N, F, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+    F: FnMut(&mut T) -> bool,
+{
+    fn drop(&mut self) {
+        unsafe {
+            let (ptr, len, _) = self.vec.ptr_mut();
+            if self.idx < self.old_len && self.del > 0 {
+                let src = ptr.add(self.idx);
+                ptr::copy(src, src.sub(self.del), self.old_len - self.idx);
+            }
+            *len = self.old_len - self.del;
+            self.vec.poison_spare(self.old_len);
+        }
+        self.vec.check_invariants();
+    }
+}
@@ -1,19 +1,30 @@
-use crate::{error::Error};
-use std::{
-    alloc::{alloc, dealloc, realloc, Layout},
-    mem::{ManuallyDrop, MaybeUninit},
-    ptr,
+use crate::{
+    allocator::{Allocator, Global},
+    drain::Drain,
+    error::{Error, Operation},
+    extend::SetLenOnDrop,
+    extract_if::ExtractIf,
+    growth::{Doubling, GrowthPolicy},
+    invariants,
+    splice::Splice,
+};
+#[cfg(feature = "alloc")]
+use alloc::{alloc::handle_alloc_error, boxed::Box, vec::Vec};
+use core::{
+    alloc::Layout,
+    marker::PhantomData,
+    mem::{self, ManuallyDrop, MaybeUninit},
+    ops::{Bound, Range, RangeBounds},
+    ptr::{self, NonNull},
+    slice,
 };
 
-union Data<T, const N: usize>
-where
-    T: Unpin,
-{
+union Data<T, const N: usize> {
     stack: ManuallyDrop<MaybeUninit<[T; N]>>,
     heap: (*mut T, usize),
 }
 
-impl<T, const N: usize> Data<T, N> where T: Unpin {
+impl<T, const N: usize> Data<T, N> {
     #[inline(always)]
     unsafe fn stack(&self) -> *const T {
         self.stack.as_ptr() as *const T
@@ -30,30 +41,285 @@ impl<T, const N: usize> Data<T, N> where T: Unpin {
     }
 }
 
-pub struct FastVec<T, const N: usize>
+/// A contiguous growable buffer which stores up to `N` items inline (i.e. on
+/// the stack) and only moves them to the heap when it runs out of space.
+///
+/// The `G` growth policy decides how much the capacity grows when the buffer
+/// runs out of space, see [`crate::growth`] for the available policies. The
+/// `A` allocator provides the heap buffer, see [`crate::allocator`].
+///
+/// # Pinning
+///
+/// Any type can be stored, including `!Unpin` types like futures, but the
+/// buffer does not support structural pinning: the items are moved when they
+/// spill to the heap, when the heap buffer is reallocated or shrunk, and by
+/// the positional editing functions. The buffer itself is `Unpin` only if the
+/// items are. To poll `!Unpin` futures in place, store them pinned on the
+/// heap (e.g. `Pin<Box<F>>`), or move them out before pinning them.
+///
+/// # Inline capacity
+///
+/// `N` must be larger than zero and smaller than `isize::MAX`, which is
+/// checked when the buffer is constructed, so an invalid capacity is a compile
+/// time error:
+///
+/// ```compile_fail
+/// let _ = fastvec::FastVec::<u32, 0>::new();
+/// ```
+///
+/// The size of the inline storage can also be limited by setting the
+/// `FASTVEC_MAX_INLINE_BYTES` environment variable at build time, so buffers
+/// with an accidentally large stack footprint fail to compile.
+pub struct FastVec<T, const N: usize, G = Doubling, A = Global>
 where
-    T: Unpin,
+    G: GrowthPolicy,
+    A: Allocator,
 {
     capacity: usize,
     data: Data<T, N>,
+    alloc: A,
+    growth: PhantomData<G>,
+    // The const constructors can't poison the spare inline slots, so they are
+    // poisoned on the first mutable access in debug-invariants mode
+    #[cfg(feature = "debug-invariants")]
+    poisoned: bool,
+    // The spill state tracked apart from the capacity in debug-invariants
+    // mode, so a corrupted length is not mistaken for a heap buffer
+    #[cfg(feature = "debug-invariants")]
+    spilled: bool,
+}
+
+// NOTE: The raw heap pointer makes the buffer !Send and !Sync by default, but
+// it owns the items just like the inline storage does, so it's safe to follow
+// the items and the allocator, just like Vec does
+
+/// The buffer can be sent to another thread if the items and the allocator
+/// can be sent.
+///
+/// ```compile_fail
+/// fn assert_send<T: Send>() {}
+/// assert_send::<fastvec::FastVec<std::rc::Rc<u32>, 4>>();
+/// ```
+unsafe impl<T, const N: usize, G, A> Send for FastVec<T, N, G, A>
+where
+    T: Send,
+    G: GrowthPolicy,
+    A: Allocator + Send,
+{
 }
 
-impl<T, const N: usize> FastVec<T, N>
+/// The buffer can be shared between threads if the items and the allocator
+/// can be shared.
+///
+/// ```compile_fail
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<fastvec::FastVec<std::cell::Cell<u32>, 4>>();
+/// ```
+unsafe impl<T, const N: usize, G, A> Sync for FastVec<T, N, G, A>
 where
-    T: Unpin,
+    T: Sync,
+    G: GrowthPolicy,
+    A: Allocator + Sync,
 {
+}
+
+impl<T, const N: usize, G> FastVec<T, N, G>
+where
+    G: GrowthPolicy,
+{
+    /// Creates a new, empty buffer which stores its items inline. It does not
+    /// allocate until more than `N` items are pushed into it.
+    ///
+    /// The function is `const`, so the buffer can be used in statics and
+    /// constants:
+    ///
+    /// ```
+    /// use fastvec::FastVec;
+    ///
+    /// static EMPTY: FastVec<u32, 4> = FastVec::new();
+    /// assert!(EMPTY.is_empty());
+    /// ```
     #[allow(clippy::new_without_default)]
     #[must_use]
-    pub fn new() -> Self {
-        debug_assert!(N > 0, "Zero length buffer is not supported");
-        debug_assert!(N < isize::MAX as usize, "Maximum legnth is isize::MAX");
+    pub const fn new() -> Self {
+        Self::new_in(Global)
+    }
+
+    /// Creates a buffer which stores the items of `array` inline. The array
+    /// can be shorter than the inline capacity, the rest of the inline
+    /// storage is left empty.
+    ///
+    /// The function is `const`, so it can build pre-filled buffers at
+    /// compile time:
+    ///
+    /// ```
+    /// use fastvec::FastVec;
+    ///
+    /// const PRIMES: FastVec<u32, 8> = FastVec::from_array([2, 3, 5, 7]);
+    /// assert_eq!(&*PRIMES, &[2, 3, 5, 7]);
+    /// assert_eq!(PRIMES.cap(), 8);
+    /// ```
+    ///
+    /// The array must fit in the inline storage, a longer array is a compile
+    /// time error:
+    ///
+    /// ```compile_fail
+    /// let _ = fastvec::FastVec::<u32, 2>::from_array([1, 2, 3]);
+    /// ```
+    #[must_use]
+    pub const fn from_array<const M: usize>(array: [T; M]) -> Self {
+        Self::from_array_in(array, Global)
+    }
+
+    /// Creates a new, empty buffer which can hold at least `capacity` items
+    /// without reallocating. If `capacity` is not larger than `N` the buffer
+    /// stays inline and no allocation is made.
+    ///
+    /// Returns an error if the capacity is too large or the allocation fails.
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, Error> {
+        Self::try_with_capacity_in(capacity, Global)
+    }
+
+    /// Creates a new, empty buffer which can hold at least `capacity` items
+    /// without reallocating. If `capacity` is not larger than `N` the buffer
+    /// stays inline and no allocation is made.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity overflows and calls `handle_alloc_error` if
+    /// the allocation fails. See [`Self::try_with_capacity`] for a fallible
+    /// version.
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_in(capacity, Global)
+    }
+
+    /// Creates a spilled buffer from the raw parts of a heap buffer allocated
+    /// by the global allocator. See [`Self::from_raw_parts_in`].
+    ///
+    /// # Safety
+    ///
+    /// The same requirements apply as for [`Self::from_raw_parts_in`].
+    pub unsafe fn from_raw_parts(ptr: *mut T, len: usize, cap: usize) -> Self {
+        Self::from_raw_parts_in(ptr, len, cap, Global)
+    }
+
+    /// Converts the buffer into a boxed slice. A spilled heap buffer is handed
+    /// over without copying the items (shrunk to fit if it has spare capacity),
+    /// inline items are moved to a new heap allocation.
+    ///
+    /// # Panics
+    ///
+    /// Calls `handle_alloc_error` if the allocation fails.
+    #[cfg(feature = "alloc")]
+    pub fn into_boxed_slice(self) -> Box<[T]> {
+        Vec::from(self).into_boxed_slice()
+    }
+}
+
+impl<T, const N: usize, G, A> FastVec<T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    /// Creates a new, empty buffer which stores its items inline and uses
+    /// `alloc` for the heap buffer once more than `N` items are pushed into it.
+    #[must_use]
+    pub const fn new_in(alloc: A) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::VALID_INLINE_CAPACITY;
 
         Self {
             capacity: 0,
-            data: Data { stack: ManuallyDrop::new(MaybeUninit::uninit()) }
+            data: Data {
+                stack: ManuallyDrop::new(MaybeUninit::uninit()),
+            },
+            alloc,
+            growth: PhantomData,
+            #[cfg(feature = "debug-invariants")]
+            poisoned: false,
+            #[cfg(feature = "debug-invariants")]
+            spilled: false,
+        }
+    }
+
+    /// Creates a buffer which stores the items of `array` inline and uses
+    /// `alloc` for the heap buffer once more than `N` items are pushed into
+    /// it. The array can be shorter than the inline capacity, but a longer
+    /// one is a compile time error.
+    #[must_use]
+    pub const fn from_array_in<const M: usize>(array: [T; M], alloc: A) -> Self {
+        // NOTE: The array is overlaid on the inline storage, so the items are
+        // moved without the const unstable pointer writes
+        union Prefix<T, const M: usize, const N: usize> {
+            array: ManuallyDrop<[T; M]>,
+            stack: ManuallyDrop<MaybeUninit<[T; N]>>,
+        }
+
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::VALID_INLINE_CAPACITY;
+        #[allow(clippy::let_unit_value)]
+        let _ = ArrayFits::<M, N>::VALID;
+
+        let prefix = Prefix::<T, M, N> {
+            array: ManuallyDrop::new(array),
+        };
+
+        Self {
+            capacity: M,
+            data: Data {
+                stack: unsafe { prefix.stack },
+            },
+            alloc,
+            growth: PhantomData,
+            #[cfg(feature = "debug-invariants")]
+            poisoned: false,
+            #[cfg(feature = "debug-invariants")]
+            spilled: false,
         }
     }
 
+    /// Creates a new, empty buffer which can hold at least `capacity` items
+    /// without reallocating, using `alloc` for the heap buffer. If `capacity`
+    /// is not larger than `N` the buffer stays inline and no allocation is
+    /// made.
+    ///
+    /// Returns an error if the capacity is too large or the allocation fails.
+    pub fn try_with_capacity_in(capacity: usize, alloc: A) -> Result<Self, Error> {
+        let mut vec = Self::new_in(alloc);
+        if capacity > vec.cap() {
+            unsafe {
+                vec.heapify(capacity, Operation::WithCapacity)?;
+            }
+        }
+
+        Ok(vec)
+    }
+
+    /// Creates a new, empty buffer which can hold at least `capacity` items
+    /// without reallocating, using `alloc` for the heap buffer. If `capacity`
+    /// is not larger than `N` the buffer stays inline and no allocation is
+    /// made.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity overflows and calls `handle_alloc_error` if
+    /// the allocation fails. See [`Self::try_with_capacity_in`] for a
+    /// fallible version.
+    #[must_use]
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        let mut vec = Self::new_in(alloc);
+        vec.grow_or_abort(capacity, true, Operation::WithCapacity);
+        vec
+    }
+
+    /// Returns a reference to the allocator of the heap buffer
+    #[inline]
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
+    /// Returns the number of items in the buffer
     pub fn len(&self) -> usize {
         unsafe {
             let (_, len, _) = self.ptr();
@@ -61,6 +327,7 @@ where
         }
     }
 
+    /// Returns the number of items the buffer can hold without reallocating
     pub fn cap(&self) -> usize {
         unsafe {
             let (_, _, cap) = self.ptr();
@@ -68,6 +335,7 @@ where
         }
     }
 
+    /// Returns `true` if the buffer contains no items
     pub fn is_empty(&self) -> bool {
         unsafe {
             let (_, len, _) = self.ptr();
@@ -75,23 +343,106 @@ where
         }
     }
 
+    /// Returns `true` if the items have been moved to the heap. Buffers of
+    /// zero-sized types never allocate, so they are never heap allocated.
     #[inline(always)]
     pub fn is_heap_allocated(&self) -> bool {
-        self.capacity > N        
+        !Self::IS_ZST && self.capacity > N
+    }
+
+    /// Returns a raw pointer to the first item, either in the inline storage
+    /// or in the heap buffer. The pointer is valid for the whole capacity,
+    /// but it's invalidated when the buffer is moved (if it's inline) or when
+    /// the items are moved to a new storage.
+    #[inline]
+    pub fn as_ptr(&self) -> *const T {
+        unsafe { self.ptr().0 }
+    }
+
+    /// Returns a raw mutable pointer to the first item, just like
+    /// [`Self::as_ptr`]. It can be used to initialize the spare capacity,
+    /// followed by [`Self::set_len`].
+    #[inline]
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        unsafe { self.ptr_mut().0 }
+    }
+
+    /// Sets the length of the buffer without dropping or initializing any
+    /// items.
+    ///
+    /// # Safety
+    ///
+    /// `new_len` must not be larger than the capacity and the items up to
+    /// `new_len` must be initialized. The items beyond it are not dropped by
+    /// the buffer anymore.
+    #[inline]
+    pub unsafe fn set_len(&mut self, new_len: usize) {
+        let (_, len, cap) = self.ptr_mut();
+        *len = new_len;
+        self.check_invariants();
+        debug_assert!(new_len <= cap, "The length exceeds the capacity");
     }
 
+    /// Returns the spare capacity of the buffer as a slice of uninitialized
+    /// items. Once they are initialized, [`Self::set_len`] can add them to the
+    /// buffer.
+    ///
+    /// ```
+    /// use fastvec::FastVec;
+    ///
+    /// let mut buf = FastVec::<u8, 16>::new();
+    /// let spare = buf.spare_capacity_mut();
+    /// spare[0].write(1);
+    /// spare[1].write(2);
+    /// unsafe { buf.set_len(2) };
+    /// assert_eq!(&*buf, &[1, 2]);
+    /// ```
+    #[inline]
+    pub fn spare_capacity_mut(&mut self) -> &mut [MaybeUninit<T>] {
+        unsafe {
+            let (ptr, len, cap) = self.ptr_mut();
+            slice::from_raw_parts_mut(ptr.add(*len) as *mut MaybeUninit<T>, cap - *len)
+        }
+    }
+
+    /// Appends an item to the back of the buffer, moving the items to the heap
+    /// if the inline storage is full.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `usize` and calls
+    /// `handle_alloc_error` if the allocation fails. See [`Self::try_push`]
+    /// for a fallible version.
     pub fn push(&mut self, value: T) {
         unsafe {
             let (mut ptr, mut len, cap) = self.ptr_mut();
             if *len == cap {
                 // We need to bump the capacity, potentially move
                 // the buf from stack to heap at this point
-                if cap > N {
-                    // NOTE: Bump capacity by about 33%, which is a hardcoded
-                    // heuristics, which might be worth revisiting
-                    self.grow(self.grow_by());
-                } else {
-                    self.heapify();
+                self.grow_or_abort(1, false, Operation::Push);
+                let &mut (heap_ptr, ref mut heap_len) = self.data.heap_mut();
+                ptr = heap_ptr;
+                len = heap_len;
+            }
+
+            ptr::write(ptr.add(*len), value);
+
+            *len += 1;
+        }
+
+        self.check_invariants();
+    }
+
+    /// Appends an item to the back of the buffer, just like [`Self::push`],
+    /// but returns an error instead of aborting if the buffer cannot grow. The
+    /// item is handed back together with the error in this case, so it's not
+    /// lost.
+    pub fn try_push(&mut self, value: T) -> Result<(), (T, Error)> {
+        unsafe {
+            let (mut ptr, mut len, cap) = self.ptr_mut();
+            if *len == cap {
+                if let Err(error) = self.try_grow(1, false, Operation::Push) {
+                    return Err((value, error));
                 }
                 let &mut (heap_ptr, ref mut heap_len) = self.data.heap_mut();
                 ptr = heap_ptr;
@@ -102,8 +453,13 @@ where
 
             *len += 1;
         }
+
+        self.check_invariants();
+        Ok(())
     }
 
+    /// Removes the last item from the buffer and returns it, or `None` if the
+    /// buffer is empty
     #[inline]
     pub fn pop(&mut self) -> Option<T> {
         unsafe {
@@ -116,64 +472,1192 @@ where
             *len = last_index;
 
             let value = ptr::read(ptr.add(last_index));
+            self.poison_spare(last_index + 1);
+            self.check_invariants();
             Some(value)
         }
     }
+
+    /// Inserts an item at position `index`, shifting all items after it to the
+    /// right. Moves the items to the heap if the inline storage is full.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, value: T) {
+        unsafe {
+            let (mut ptr, mut len, cap) = self.ptr_mut();
+            if index > *len {
+                panic!(
+                    "insertion index (is {}) should be <= len (is {})",
+                    index, *len
+                );
+            }
+            if *len == cap {
+                self.grow_or_abort(1, false, Operation::Insert);
+                let &mut (heap_ptr, ref mut heap_len) = self.data.heap_mut();
+                ptr = heap_ptr;
+                len = heap_len;
+            }
+
+            let hole = ptr.add(index);
+            ptr::copy(hole, hole.add(1), *len - index);
+            ptr::write(hole, value);
+
+            *len += 1;
+        }
+
+        self.check_invariants();
+    }
+
+    /// Removes and returns the item at position `index`, shifting all items
+    /// after it to the left.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn remove(&mut self, index: usize) -> T {
+        unsafe {
+            let (ptr, len, _) = self.ptr_mut();
+            if index >= *len {
+                panic!("removal index (is {}) should be < len (is {})", index, *len);
+            }
+
+            let hole = ptr.add(index);
+            let value = ptr::read(hole);
+            ptr::copy(hole.add(1), hole, *len - index - 1);
+
+            let old_len = mem::replace(len, *len - 1);
+            self.poison_spare(old_len);
+            self.check_invariants();
+
+            value
+        }
+    }
+
+    /// Removes and returns the item at position `index` and replaces it with
+    /// the last item. This does not preserve ordering, but is O(1).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        unsafe {
+            let (ptr, len, _) = self.ptr_mut();
+            if index >= *len {
+                panic!(
+                    "swap_remove index (is {}) should be < len (is {})",
+                    index, *len
+                );
+            }
+
+            let last_index = *len - 1;
+            let hole = ptr.add(index);
+            let value = ptr::read(hole);
+            ptr::copy(ptr.add(last_index), hole, 1);
+
+            *len = last_index;
+            self.poison_spare(last_index + 1);
+            self.check_invariants();
+
+            value
+        }
+    }
+
+    /// Shortens the buffer to `len` items and drops the rest. Does nothing if
+    /// `len` is greater than the current length. The capacity is unchanged.
+    pub fn truncate(&mut self, len: usize) {
+        unsafe {
+            let (ptr, cur_len, _) = self.ptr_mut();
+            if len >= *cur_len {
+                return;
+            }
+
+            let tail = ptr::slice_from_raw_parts_mut(ptr.add(len), *cur_len - len);
+            // NOTE: The length is updated first, so a panicking drop does
+            // not result in double drops
+            let old_len = mem::replace(cur_len, len);
+            ptr::drop_in_place(tail);
+            self.poison_spare(old_len);
+        }
+
+        self.check_invariants();
+    }
+
+    /// Drops all items in the buffer. The capacity is unchanged, so a spilled
+    /// buffer keeps its heap allocation.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Retains only the items for which the predicate returns `true` and drops
+    /// the rest, preserving the order of the retained items.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.retain_mut(|item| f(item));
+    }
+
+    /// Retains only the items for which the predicate returns `true`, just
+    /// like [`Self::retain`], but passes a mutable reference to the predicate.
+    pub fn retain_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        let original_len = self.len();
+        if original_len == 0 {
+            return;
+        }
+
+        // NOTE: The length is zeroed for the duration of the filtering, so
+        // leaking the guard can only leak items, but never double drop them
+        unsafe {
+            let (_, len, _) = self.ptr_mut();
+            *len = 0;
+        }
+
+        // Moves the unprocessed tail over the hole left by the deleted items
+        // and restores the length, even if the predicate or a drop panics
+        struct BackshiftOnDrop<'a, T, const N: usize, G: GrowthPolicy, A: Allocator> {
+            vec: &'a mut FastVec<T, N, G, A>,
+            processed: usize,
+            deleted: usize,
+            original_len: usize,
+        }
+
+        impl<'a, T, const N: usize, G: GrowthPolicy, A: Allocator> Drop
+            for BackshiftOnDrop<'a, T, N, G, A>
+        {
+            fn drop(&mut self) {
+                unsafe {
+                    let (ptr, len, _) = self.vec.ptr_mut();
+                    if self.deleted > 0 {
+                        ptr::copy(
+                            ptr.add(self.processed),
+                            ptr.add(self.processed - self.deleted),
+                            self.original_len - self.processed,
+                        );
+                    }
+                    *len = self.original_len - self.deleted;
+                    self.vec.poison_spare(self.original_len);
+                }
+                self.vec.check_invariants();
+            }
+        }
+
+        let mut guard = BackshiftOnDrop {
+            vec: self,
+            processed: 0,
+            deleted: 0,
+            original_len,
+        };
+        let (ptr, _, _) = unsafe { guard.vec.ptr_mut() };
+
+        while guard.processed != original_len {
+            let cur = unsafe { &mut *ptr.add(guard.processed) };
+            if !f(cur) {
+                guard.processed += 1;
+                guard.deleted += 1;
+                unsafe { ptr::drop_in_place(cur) };
+            } else {
+                if guard.deleted > 0 {
+                    unsafe {
+                        let hole = ptr.add(guard.processed - guard.deleted);
+                        ptr::copy_nonoverlapping(cur, hole, 1);
+                    }
+                }
+                guard.processed += 1;
+            }
+        }
+
+        mem::drop(guard);
+    }
+
+    /// Removes consecutive repeated items, keeping only the first one of each
+    /// run.
+    #[inline]
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        self.dedup_by(|a, b| a == b);
+    }
+
+    /// Removes consecutive items which resolve to the same key, keeping only
+    /// the first one of each run.
+    #[inline]
+    pub fn dedup_by_key<F, K>(&mut self, mut key: F)
+    where
+        F: FnMut(&mut T) -> K,
+        K: PartialEq,
+    {
+        self.dedup_by(|a, b| key(a) == key(b));
+    }
+
+    /// Removes consecutive items for which `same_bucket` returns `true`. The
+    /// predicate receives the current item first and the last retained item
+    /// second, and the current item is dropped if it returns `true`.
+    pub fn dedup_by<F>(&mut self, mut same_bucket: F)
+    where
+        F: FnMut(&mut T, &mut T) -> bool,
+    {
+        let original_len = self.len();
+        if original_len <= 1 {
+            return;
+        }
+
+        // Moves the unprocessed tail over the gap left by the removed items
+        // and fixes up the length, even if the predicate or a drop panics
+        struct FillGapOnDrop<'a, T, const N: usize, G: GrowthPolicy, A: Allocator> {
+            read: usize,
+            write: usize,
+            original_len: usize,
+            vec: &'a mut FastVec<T, N, G, A>,
+        }
+
+        impl<'a, T, const N: usize, G: GrowthPolicy, A: Allocator> Drop for FillGapOnDrop<'a, T, N, G, A> {
+            fn drop(&mut self) {
+                unsafe {
+                    let (ptr, len, _) = self.vec.ptr_mut();
+                    ptr::copy(
+                        ptr.add(self.read),
+                        ptr.add(self.write),
+                        self.original_len - self.read,
+                    );
+                    *len = self.original_len - (self.read - self.write);
+                    self.vec.poison_spare(self.original_len);
+                }
+                self.vec.check_invariants();
+            }
+        }
+
+        let mut gap = FillGapOnDrop {
+            read: 1,
+            write: 1,
+            original_len,
+            vec: self,
+        };
+        let (ptr, _, _) = unsafe { gap.vec.ptr_mut() };
+
+        unsafe {
+            while gap.read < original_len {
+                let read_ptr = ptr.add(gap.read);
+                let prev_ptr = ptr.add(gap.write - 1);
+                if same_bucket(&mut *read_ptr, &mut *prev_ptr) {
+                    // NOTE: Bumped before the drop, so a panicking drop
+                    // doesn't lead to a double drop in the guard
+                    gap.read += 1;
+                    ptr::drop_in_place(read_ptr);
+                } else {
+                    ptr::copy(read_ptr, ptr.add(gap.write), 1);
+                    gap.write += 1;
+                    gap.read += 1;
+                }
+            }
+
+            let (_, len, _) = gap.vec.ptr_mut();
+            *len = gap.write;
+            gap.vec.poison_spare(original_len);
+            gap.vec.check_invariants();
+            mem::forget(gap);
+        }
+    }
+
+    /// Creates an iterator which removes and yields the items in `range` for
+    /// which the predicate returns `true`. The rest of the items stay in the
+    /// buffer in their original order.
+    ///
+    /// The items are only visited as the iterator advances. If it is dropped
+    /// early, the remaining items are retained. If it is leaked, the buffer
+    /// may lose items, but it stays valid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn extract_if<F, R>(&mut self, range: R, filter: F) -> ExtractIf<'_, T, N, F, G, A>
+    where
+        F: FnMut(&mut T) -> bool,
+        R: RangeBounds<usize>,
+    {
+        let old_len = self.len();
+        let Range { start, end } = slice_range(range, old_len);
+
+        // NOTE: Zero the length to avoid double drops if the iterator leaks
+        unsafe {
+            let (_, len, _) = self.ptr_mut();
+            *len = 0;
+        }
+
+        ExtractIf::new(self, start, end, old_len, filter)
+    }
+
+    /// Removes the items in `range` from the buffer and returns them as an
+    /// iterator. The items after the range are moved back in place when the
+    /// iterator is dropped, and the items not yielded by then are dropped.
+    ///
+    /// If the iterator is leaked, the buffer is cut at the start of the range
+    /// and the rest of the items are leaked.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn drain<R>(&mut self, range: R) -> Drain<'_, T, N, G, A>
+    where
+        R: RangeBounds<usize>,
+    {
+        let len = self.len();
+        let Range { start, end } = slice_range(range, len);
+
+        unsafe {
+            // NOTE: The iterator is derived from the same pointer the Drain
+            // uses later to access the buffer
+            let mut vec = NonNull::from(self);
+
+            // NOTE: Cut the buffer at the start of the range first, so leaking
+            // the Drain doesn't leave yielded items in the buffer
+            let (ptr, cur_len, _) = vec.as_mut().ptr_mut();
+            *cur_len = start;
+            let range_slice = slice::from_raw_parts(ptr.add(start), end - start);
+
+            Drain {
+                tail_start: end,
+                tail_len: len - end,
+                iter: range_slice.iter(),
+                vec,
+            }
+        }
+    }
+
+    /// Replaces the items in `range` with the items of `replace_with` and
+    /// returns the removed items as an iterator. The replacement happens when
+    /// the iterator is dropped, and the items not yielded by then are dropped.
+    ///
+    /// The lower bound of the size hint of `replace_with` is used to make room
+    /// for the new items, so the tail is moved only once if it's exact.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    #[inline]
+    pub fn splice<R, I>(&mut self, range: R, replace_with: I) -> Splice<'_, I::IntoIter, N, G, A>
+    where
+        R: RangeBounds<usize>,
+        I: IntoIterator<Item = T>,
+    {
+        Splice {
+            drain: self.drain(range),
+            replace_with: replace_with.into_iter(),
+        }
+    }
+
+    /// Reserves capacity for at least `additional` more items. The buffer may
+    /// reserve more space than requested to avoid frequent reallocations. Does
+    /// nothing if the capacity is already sufficient.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `usize` and calls
+    /// `handle_alloc_error` if the allocation fails. See
+    /// [`Self::try_reserve`] for a fallible version.
+    pub fn reserve(&mut self, additional: usize) {
+        self.grow_or_abort(additional, false, Operation::Reserve);
+        self.check_invariants();
+    }
+
+    /// Reserves capacity for exactly `additional` more items. Does nothing if
+    /// the capacity is already sufficient.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `usize` and calls
+    /// `handle_alloc_error` if the allocation fails. See
+    /// [`Self::try_reserve_exact`] for a fallible version.
+    pub fn reserve_exact(&mut self, additional: usize) {
+        self.grow_or_abort(additional, true, Operation::ReserveExact);
+        self.check_invariants();
+    }
+
+    /// Shrinks the capacity as much as possible. If the items fit in the
+    /// inline storage, they are moved back and the heap buffer is freed.
+    ///
+    /// # Panics
+    ///
+    /// Calls `handle_alloc_error` if the reallocation fails.
+    #[inline]
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink_to(0);
+    }
+
+    /// Shrinks the capacity to the larger of `min_capacity` and the length.
+    /// If that fits in the inline storage, the items are moved back and the
+    /// heap buffer is freed. Does nothing if the capacity is already smaller.
+    ///
+    /// # Panics
+    ///
+    /// Calls `handle_alloc_error` if the reallocation fails.
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        if !self.is_heap_allocated() {
+            return;
+        }
+
+        let cap = self.len().max(min_capacity);
+        unsafe {
+            if cap <= N {
+                self.move_inline();
+            } else if cap < self.capacity {
+                self.shrink(cap);
+            }
+        }
+
+        self.check_invariants();
+    }
+
+    /// Tries to reserve capacity for at least `additional` more items. The
+    /// buffer may reserve more space than requested to avoid frequent
+    /// reallocations. Does nothing if the capacity is already sufficient.
+    ///
+    /// Returns an error if the capacity overflows or the allocation fails, in
+    /// which case the buffer is left unchanged.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), Error> {
+        let result = self.try_grow(additional, false, Operation::Reserve);
+        self.check_invariants();
+        result
+    }
+
+    /// Tries to reserve capacity for exactly `additional` more items. Does
+    /// nothing if the capacity is already sufficient.
+    ///
+    /// Returns an error if the capacity overflows or the allocation fails, in
+    /// which case the buffer is left unchanged.
+    pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), Error> {
+        let result = self.try_grow(additional, true, Operation::ReserveExact);
+        self.check_invariants();
+        result
+    }
+
+    /// Moves the items to the heap, even if they fit in the inline storage.
+    /// The heap capacity is decided by the growth policy, as if the inline
+    /// storage ran out of space. Does nothing if the buffer is already heap
+    /// allocated or stores zero-sized types, which never allocate.
+    ///
+    /// # Panics
+    ///
+    /// Calls `handle_alloc_error` if the allocation fails. See
+    /// [`Self::try_spill`] for a fallible version.
+    pub fn spill(&mut self) {
+        if let Err(error) = self.try_spill() {
+            abort_on_error(error);
+        }
+    }
+
+    /// Moves the items to the heap, just like [`Self::spill`], but returns an
+    /// error instead of aborting if the allocation fails. The buffer is left
+    /// unchanged in this case.
+    pub fn try_spill(&mut self) -> Result<(), Error> {
+        if Self::IS_ZST || self.is_heap_allocated() {
+            return Ok(());
+        }
+
+        let cap = G::spill_capacity::<T>(N, N + 1).max(N + 1);
+        let result = unsafe { self.heapify(cap, Operation::Spill) };
+        self.check_invariants();
+        result
+    }
+
+    /// Moves the items back to the inline storage and frees the heap buffer
+    /// if they fit in it. Returns `true` if the items are stored inline
+    /// afterwards.
+    pub fn try_inline(&mut self) -> bool {
+        if !self.is_heap_allocated() {
+            return true;
+        }
+        if self.len() > N {
+            return false;
+        }
+
+        unsafe {
+            self.move_inline();
+        }
+        self.check_invariants();
+        true
+    }
+
+    /// Converts the buffer into one with an inline capacity of `M` items,
+    /// without cloning the items. A heap buffer larger than `M` is handed over
+    /// as is, otherwise the items are moved to the inline storage of the new
+    /// buffer if they fit, or to a new heap buffer if they don't.
+    ///
+    /// # Panics
+    ///
+    /// Calls `handle_alloc_error` if the allocation of the new heap buffer
+    /// fails, in which case the items are leaked.
+    pub fn into_inline_capacity<const M: usize>(self) -> FastVec<T, M, G, A> {
+        let this = ManuallyDrop::new(self);
+
+        unsafe {
+            let alloc = ptr::read(&this.alloc);
+            let mut vec = FastVec::<T, M, G, A>::new_in(alloc);
+
+            if this.is_heap_allocated() && this.capacity > M {
+                vec.data = Data {
+                    heap: this.data.heap,
+                };
+                vec.capacity = this.capacity;
+                #[cfg(feature = "debug-invariants")]
+                {
+                    vec.spilled = true;
+                }
+            } else {
+                let (src, len, cap) = this.ptr();
+                vec.grow_or_abort(len, false, Operation::Spill);
+
+                let (dst, vec_len, _) = vec.ptr_mut();
+                ptr::copy_nonoverlapping(src, dst, len);
+                *vec_len = len;
+
+                // NOTE: The allocator was moved to the new buffer, which frees
+                // the old heap buffer with it
+                if this.is_heap_allocated() {
+                    vec.deallocate_heap(src as *mut T, cap);
+                }
+            }
+
+            vec.check_invariants();
+            vec
+        }
+    }
+
+    /// Returns the inline array if the buffer is stored inline and it's full,
+    /// otherwise returns the buffer itself. See [`Self::into_array`] for
+    /// extracting an array of any length.
+    pub fn into_inner(self) -> Result<[T; N], Self> {
+        if self.is_heap_allocated() || self.len() != N {
+            return Err(self);
+        }
+
+        self.into_array()
+    }
+
+    /// Moves the items into an array if the buffer holds exactly `M` items,
+    /// otherwise returns the buffer itself. The heap buffer is freed if the
+    /// buffer spilled.
+    pub fn into_array<const M: usize>(mut self) -> Result<[T; M], Self> {
+        if self.len() != M {
+            return Err(self);
+        }
+
+        unsafe {
+            let (ptr, len, _) = self.ptr_mut();
+            let array = ptr::read(ptr as *const [T; M]);
+            // NOTE: The items are moved out, so only the storage is freed
+            *len = 0;
+            Ok(array)
+        }
+    }
+
+    /// Creates a spilled buffer from the raw parts of a heap buffer holding
+    /// `len` items with a capacity of `cap` items, using `alloc` to manage it.
+    /// See [`Self::into_raw_parts_with_alloc`].
+    ///
+    /// In `debug-invariants` mode the heap buffer is reallocated to make room
+    /// for the guard areas around the items, so they may be moved.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to the start of a heap buffer allocated by `alloc`
+    /// with the layout of `cap` items, like the buffer of a `Vec` using the
+    /// global allocator or the parts returned by
+    /// [`Self::into_raw_parts_with_alloc`]. `cap` must be larger than `N`,
+    /// `len` must not be larger than `cap`, and the first `len` items must be
+    /// initialized. The buffer takes ownership of the items and the heap
+    /// buffer.
+    pub unsafe fn from_raw_parts_in(ptr: *mut T, len: usize, cap: usize, alloc: A) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::VALID_INLINE_CAPACITY;
+
+        debug_assert!(!Self::IS_ZST, "Zero-sized types are never heap allocated");
+        debug_assert!(cap > N, "Heap capacity must be larger than the inline one");
+        debug_assert!(len <= cap, "The length exceeds the capacity");
+
+        let mut vec = Self {
+            capacity: cap,
+            data: Data { heap: (ptr, len) },
+            alloc,
+            growth: PhantomData,
+            #[cfg(feature = "debug-invariants")]
+            poisoned: false,
+            #[cfg(feature = "debug-invariants")]
+            spilled: true,
+        };
+        vec.data.heap.0 = vec.attach_guard(ptr, cap);
+        vec.check_invariants();
+        vec
+    }
+
+    /// Decomposes a spilled buffer into the pointer to its heap buffer, the
+    /// length, the capacity and the allocator, without dropping the items.
+    /// Returns the buffer itself if it's stored inline, which can be moved to
+    /// the heap first with [`Self::spill`].
+    ///
+    /// The pointer points to the start of a heap buffer allocated by the
+    /// allocator with the layout of `cap` items in every build, so with the
+    /// global allocator it can also be turned into a `Vec` by
+    /// `Vec::from_raw_parts`. The caller becomes responsible for the items and
+    /// the heap buffer, which can be turned back into a buffer with
+    /// [`Self::from_raw_parts_in`].
+    pub fn into_raw_parts_with_alloc(self) -> Result<(*mut T, usize, usize, A), Self> {
+        if !self.is_heap_allocated() {
+            return Err(self);
+        }
+        self.check_invariants();
+
+        let this = ManuallyDrop::new(self);
+        unsafe {
+            let (ptr, len) = this.data.heap;
+            let ptr = this.detach_guard(ptr, this.capacity);
+            Ok((ptr, len, this.capacity, ptr::read(&this.alloc)))
+        }
+    }
+
+    /// Decomposes a spilled buffer into the pointer to its heap buffer, the
+    /// length and the capacity, just like [`Self::into_raw_parts_with_alloc`],
+    /// but drops the allocator. Returns the buffer itself if it's stored
+    /// inline.
+    pub fn into_raw_parts(self) -> Result<(*mut T, usize, usize), Self> {
+        self.into_raw_parts_with_alloc()
+            .map(|(ptr, len, cap, _)| (ptr, len, cap))
+    }
+
+    /// Clones and appends all items in the slice to the buffer.
+    ///
+    /// Returns an error if the capacity overflows or the allocation fails, in
+    /// which case the buffer is left unchanged.
+    pub fn try_extend_from_slice(&mut self, other: &[T]) -> Result<(), Error>
+    where
+        T: Clone,
+    {
+        self.try_grow(other.len(), false, Operation::Extend)?;
+
+        unsafe {
+            self.clone_from_slice_unchecked(other);
+        }
+
+        self.check_invariants();
+        Ok(())
+    }
+
+    /// Clones and appends all items in the slice to the buffer. The capacity is
+    /// reserved once up front. See [`Self::extend_from_copy_slice`] for a
+    /// faster version for `Copy` types.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `usize` and calls
+    /// `handle_alloc_error` if the allocation fails.
+    pub fn extend_from_slice(&mut self, other: &[T])
+    where
+        T: Clone,
+    {
+        self.grow_or_abort(other.len(), false, Operation::Extend);
+
+        unsafe {
+            self.clone_from_slice_unchecked(other);
+        }
+
+        self.check_invariants();
+    }
+
+    /// Copies and appends all items in the slice to the buffer with a single
+    /// memory copy. The capacity is reserved once up front.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `usize` and calls
+    /// `handle_alloc_error` if the allocation fails.
+    pub fn extend_from_copy_slice(&mut self, other: &[T])
+    where
+        T: Copy,
+    {
+        self.grow_or_abort(other.len(), false, Operation::Extend);
+
+        unsafe {
+            let (ptr, len, _) = self.ptr_mut();
+            ptr::copy_nonoverlapping(other.as_ptr(), ptr.add(*len), other.len());
+            *len += other.len();
+        }
+
+        self.check_invariants();
+    }
+
+    /// Clones the items in `src` and appends them to the buffer. See
+    /// [`Self::extend_from_within_copy`] for a faster version for `Copy` types.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds, if the new capacity overflows
+    /// `usize` and calls `handle_alloc_error` if the allocation fails.
+    pub fn extend_from_within<R>(&mut self, src: R)
+    where
+        T: Clone,
+        R: RangeBounds<usize>,
+    {
+        let Range { start, end } = slice_range(src, self.len());
+        self.grow_or_abort(end - start, false, Operation::Extend);
+
+        unsafe {
+            let (ptr, len, _) = self.ptr_mut();
+            let src = slice::from_raw_parts(ptr.add(start), end - start);
+            let mut guard = SetLenOnDrop::new(len);
+            for item in src {
+                ptr::write(ptr.add(guard.local_len), item.clone());
+                guard.local_len += 1;
+            }
+        }
+
+        self.check_invariants();
+    }
+
+    /// Copies the items in `src` and appends them to the buffer with a single
+    /// memory copy.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds, if the new capacity overflows
+    /// `usize` and calls `handle_alloc_error` if the allocation fails.
+    pub fn extend_from_within_copy<R>(&mut self, src: R)
+    where
+        T: Copy,
+        R: RangeBounds<usize>,
+    {
+        let Range { start, end } = slice_range(src, self.len());
+        self.grow_or_abort(end - start, false, Operation::Extend);
+
+        unsafe {
+            let (ptr, len, _) = self.ptr_mut();
+            ptr::copy_nonoverlapping(ptr.add(start), ptr.add(*len), end - start);
+            *len += end - start;
+        }
+
+        self.check_invariants();
+    }
 }
 
-impl<T, const N: usize> FastVec<T, N>
+impl<T, const N: usize, G, A> FastVec<T, N, G, A>
 where
-    T: Unpin,
+    G: GrowthPolicy,
+    A: Allocator,
 {
-    unsafe fn heapify(&mut self) -> Result<(), Error> {
-        debug_assert!(self.cap() <= N, "Already heap allocated");
+    // Clones the items into the spare capacity, which must be large enough to
+    // hold all of them
+    unsafe fn clone_from_slice_unchecked(&mut self, other: &[T])
+    where
+        T: Clone,
+    {
+        let (ptr, len, _) = self.ptr_mut();
+        // NOTE: The length is stored even if a clone() panics halfway, so the
+        // items cloned so far are not leaked
+        let mut guard = SetLenOnDrop::new(len);
+        for item in other {
+            ptr::write(ptr.add(guard.local_len), item.clone());
+            guard.local_len += 1;
+        }
+    }
 
-        let (src, len, cap) = self.ptr();
+    // Calculates the new capacity needed to store `additional` more items or
+    // returns `None` if the current capacity is enough
+    fn required_capacity(
+        &self,
+        additional: usize,
+        exact: bool,
+        operation: Operation,
+    ) -> Result<Option<usize>, Error> {
+        let (_, len, cap) = unsafe { self.ptr() };
+        let required = len.checked_add(additional).ok_or(Error::CapacityOverflow {
+            operation,
+            capacity: cap,
+        })?;
+        if required <= cap {
+            return Ok(None);
+        }
 
-        let cap = cap + self.grow_by();
-        let ptr = {
-            let layout = Layout::array::<T>(cap).map_err(|_| Error::LayoutError)?;
-            alloc(layout) as *mut T
-        };
+        if exact {
+            Ok(Some(required))
+        } else if self.is_heap_allocated() {
+            Ok(Some(G::grow_capacity::<T>(cap, required).max(required)))
+        } else {
+            Ok(Some(G::spill_capacity::<T>(cap, required).max(required)))
+        }
+    }
+
+    // Moves the items to the heap or reallocates the heap buffer to have room
+    // for `cap` items in total
+    unsafe fn set_capacity(&mut self, cap: usize, operation: Operation) -> Result<(), Error> {
+        if self.is_heap_allocated() {
+            self.grow(cap - self.capacity, operation)
+        } else {
+            self.heapify(cap, operation)
+        }
+    }
 
+    fn try_grow(
+        &mut self,
+        additional: usize,
+        exact: bool,
+        operation: Operation,
+    ) -> Result<(), Error> {
+        match self.required_capacity(additional, exact, operation)? {
+            Some(cap) => unsafe { self.set_capacity(cap, operation) },
+            None => Ok(()),
+        }
+    }
+
+    // The infallible counterpart of try_grow(), which panics on capacity
+    // overflow and calls the global allocation error handler on OOM
+    pub(crate) fn grow_or_abort(&mut self, additional: usize, exact: bool, operation: Operation) {
+        if let Err(error) = self.try_grow(additional, exact, operation) {
+            abort_on_error(error);
+        }
+    }
+
+    unsafe fn heapify(&mut self, cap: usize, operation: Operation) -> Result<(), Error> {
+        debug_assert!(self.capacity <= N, "Already heap allocated");
+        debug_assert!(cap > N, "Heap capacity must be larger than the inline one");
+
+        let (src, len, capacity) = self.ptr();
+
+        let ptr = self.allocate_heap(cap, operation, capacity)?;
         ptr::copy_nonoverlapping(src, ptr, len);
 
         self.data.heap = (ptr, self.capacity);
         self.capacity = cap;
+        #[cfg(feature = "debug-invariants")]
+        {
+            self.spilled = true;
+        }
 
         Ok(())
     }
 
-    unsafe fn grow(&mut self, additional: usize) -> Result<(), Error> {
+    unsafe fn grow(&mut self, additional: usize, operation: Operation) -> Result<(), Error> {
         debug_assert!(self.capacity > N, "Not heap allocated");
 
-        let (ptr, len, mut cap) = self.ptr_mut();
-
-        let layout = Layout::array::<T>(cap).map_err(|_| Error::LayoutError)?;
-        cap = cap
+        let (ptr, len, capacity) = self.ptr();
+        let cap = capacity
             .checked_add(additional)
-            .ok_or(Error::CapacityOverflow)?;
-        let size = Layout::array::<T>(cap)
-            .map_err(|_| Error::LayoutError)?
-            .size();
+            .ok_or(Error::CapacityOverflow {
+                operation,
+                capacity,
+            })?;
+
+        // NOTE: The original allocation is left untouched on failure
+        let ptr = self.grow_heap(ptr as *mut T, capacity, cap, operation)?;
+        self.data = Data { heap: (ptr, len) };
+        self.capacity = cap;
+
+        Ok(())
+    }
+
+    // Reallocates the heap buffer to a smaller capacity, which is still
+    // larger than the inline storage
+    unsafe fn shrink(&mut self, cap: usize) {
+        debug_assert!(self.capacity > N, "Not heap allocated");
+        debug_assert!(cap > N, "Heap capacity must be larger than the inline one");
+
+        let (ptr, len, old_cap) = self.ptr();
+        debug_assert!(len <= cap, "Shrinking below the length");
+
+        let ptr = self.shrink_heap(ptr as *mut T, old_cap, cap);
+        self.data = Data { heap: (ptr, len) };
+        self.capacity = cap;
+    }
+
+    // Moves the items from the heap back to the inline storage and frees the
+    // heap buffer
+    unsafe fn move_inline(&mut self) {
+        debug_assert!(self.capacity > N, "Not heap allocated");
+
+        let (ptr, len) = self.data.heap;
+        let cap = self.capacity;
+        debug_assert!(len <= N, "The items don't fit in the inline storage");
+
+        // NOTE: The inline storage overlaps the heap pointer and length, which
+        // are already copied out at this point
+        ptr::copy_nonoverlapping(ptr, self.data.stack_mut(), len);
+        invariants::poison(self.data.stack_mut().add(len), N - len);
+        self.capacity = len;
+        #[cfg(feature = "debug-invariants")]
+        {
+            self.spilled = false;
+        }
+
+        self.deallocate_heap(ptr, cap);
+    }
+
+    // NOTE: All heap allocations go through the functions below, so the heap
+    // buffer can be surrounded by guard areas in debug-invariants mode. The
+    // errors report the layout of the items, without the guard areas.
+
+    // Allocates a heap buffer for `cap` items
+    unsafe fn allocate_heap(
+        &self,
+        cap: usize,
+        operation: Operation,
+        capacity: usize,
+    ) -> Result<*mut T, Error> {
+        if !self.alloc.can_allocate() {
+            return Err(Error::CapacityOverflow {
+                operation,
+                capacity,
+            });
+        }
+
+        let (layout, offset) =
+            invariants::heap_layout::<T>(cap).map_err(|source| Error::LayoutError {
+                source,
+                operation,
+                capacity,
+            })?;
+        let base = self.alloc.allocate(layout).map_err(|_| Error::AllocError {
+            layout: Layout::array::<T>(cap).unwrap(),
+            operation,
+            capacity,
+        })?;
+
+        let ptr = base.as_ptr().add(offset) as *mut T;
+        invariants::write_canaries(ptr, cap);
+        invariants::poison(ptr, cap);
+
+        Ok(ptr)
+    }
+
+    // Reallocates the heap buffer of `old_cap` items to hold `cap` items,
+    // leaving the original buffer untouched on failure
+    unsafe fn grow_heap(
+        &self,
+        ptr: *mut T,
+        old_cap: usize,
+        cap: usize,
+        operation: Operation,
+    ) -> Result<*mut T, Error> {
+        invariants::check_canaries(ptr, old_cap);
+
+        // NOTE: This is the current layout, this shouldn't fail
+        let (layout, offset) = invariants::heap_layout::<T>(old_cap).unwrap();
+        let (new_layout, _) =
+            invariants::heap_layout::<T>(cap).map_err(|source| Error::LayoutError {
+                source,
+                operation,
+                capacity: old_cap,
+            })?;
 
         // We need to guarantee the following:
         // * We don't ever allocate `> isize::MAX` byte-size objects.
         // * We don't overflow `usize::MAX` and actually allocate too little.
-        if usize::BITS < 64 && size > isize::MAX as usize {
-            return Err(Error::CapacityOverflow);
+        if usize::BITS < 64 && new_layout.size() > isize::MAX as usize {
+            return Err(Error::CapacityOverflow {
+                operation,
+                capacity: old_cap,
+            });
         }
 
-        let ptr = realloc(ptr as *mut u8, layout, size) as *mut T;
-        self.data = Data { heap: (ptr, *len) };
-        self.capacity = cap;
+        let base = NonNull::new_unchecked((ptr as *mut u8).sub(offset));
+        let base = self
+            .alloc
+            .grow(base, layout, new_layout)
+            .map_err(|_| Error::AllocError {
+                layout: Layout::array::<T>(cap).unwrap(),
+                operation,
+                capacity: old_cap,
+            })?;
 
-        Ok(())
+        let ptr = base.as_ptr().add(offset) as *mut T;
+        invariants::write_canaries(ptr, cap);
+        invariants::poison(ptr.add(old_cap), cap - old_cap);
+
+        Ok(ptr)
+    }
+
+    // Reallocates the heap buffer of `old_cap` items to hold only `cap` items,
+    // calling the allocation error handler on failure
+    unsafe fn shrink_heap(&self, ptr: *mut T, old_cap: usize, cap: usize) -> *mut T {
+        invariants::check_canaries(ptr, old_cap);
+
+        // NOTE: These are the current and a smaller layout, so they can't fail
+        let (layout, offset) = invariants::heap_layout::<T>(old_cap).unwrap();
+        let (new_layout, _) = invariants::heap_layout::<T>(cap).unwrap();
+
+        let base = NonNull::new_unchecked((ptr as *mut u8).sub(offset));
+        let base = match self.alloc.shrink(base, layout, new_layout) {
+            Ok(base) => base,
+            Err(_) => alloc_error(Layout::array::<T>(cap).unwrap()),
+        };
+
+        let ptr = base.as_ptr().add(offset) as *mut T;
+        invariants::write_canaries(ptr, cap);
+
+        ptr
+    }
+
+    // Reallocates a heap buffer of `cap` items allocated with the layout of the
+    // items alone to the layout used by the buffer, moving the items between
+    // the guard areas in debug-invariants mode. Does nothing otherwise.
+    unsafe fn attach_guard(&self, ptr: *mut T, cap: usize) -> *mut T {
+        // NOTE: The items are already allocated, so the layout is valid
+        let layout = Layout::array::<T>(cap).unwrap();
+        let (guarded, offset) = invariants::heap_layout::<T>(cap).unwrap();
+        if guarded == layout {
+            return ptr;
+        }
+
+        let base = NonNull::new_unchecked(ptr as *mut u8);
+        let base = match self.alloc.grow(base, layout, guarded) {
+            Ok(base) => base.as_ptr(),
+            Err(_) => alloc_error(guarded),
+        };
+
+        let ptr = base.add(offset) as *mut T;
+        ptr::copy(base as *const T, ptr, cap);
+        invariants::write_canaries(ptr, cap);
+
+        ptr
     }
 
+    // Moves the items of the heap buffer of `cap` items to the start of the
+    // allocation and reallocates it to the layout of the items alone before
+    // it's handed over, the reverse of attach_guard()
+    unsafe fn detach_guard(&self, ptr: *mut T, cap: usize) -> *mut T {
+        invariants::check_canaries(ptr, cap);
+
+        // NOTE: This is the current layout, this shouldn't fail
+        let (guarded, offset) = invariants::heap_layout::<T>(cap).unwrap();
+        let layout = Layout::array::<T>(cap).unwrap();
+        if guarded == layout {
+            return ptr;
+        }
+
+        let base = (ptr as *mut u8).sub(offset);
+        ptr::copy(ptr, base as *mut T, cap);
+        match self
+            .alloc
+            .shrink(NonNull::new_unchecked(base), guarded, layout)
+        {
+            Ok(base) => base.as_ptr() as *mut T,
+            Err(_) => alloc_error(layout),
+        }
+    }
+
+    // Frees the heap buffer of `cap` items
+    unsafe fn deallocate_heap(&self, ptr: *mut T, cap: usize) {
+        invariants::check_canaries(ptr, cap);
+
+        // NOTE: This is the current layout, this shouldn't fail
+        let (layout, offset) = invariants::heap_layout::<T>(cap).unwrap();
+        let base = NonNull::new_unchecked((ptr as *mut u8).sub(offset));
+        self.alloc.deallocate(base, layout);
+    }
+
+    // Validates the internal state in debug-invariants mode and panics if it's
+    // inconsistent. Does nothing otherwise.
+    #[inline(always)]
+    pub(crate) fn check_invariants(&self) {
+        #[cfg(feature = "debug-invariants")]
+        unsafe {
+            // NOTE: This is checked first, so a length written past the inline
+            // capacity is caught before the inline storage is read as the
+            // heap pointer and length
+            assert!(
+                self.spilled == self.is_heap_allocated(),
+                "Invariant violated: the buffer is {} but its capacity field is {}",
+                if self.spilled { "spilled" } else { "inline" },
+                self.capacity
+            );
+
+            let (ptr, len, cap) = self.ptr();
+            if Self::IS_ZST {
+                // NOTE: Only the length is stored for zero-sized types
+                assert!(
+                    len == self.capacity
+                        && cap == usize::MAX
+                        && ptr == NonNull::dangling().as_ptr(),
+                    "Invariant violated: zero-sized buffer stores more than its length"
+                );
+                return;
+            }
+
+            assert!(
+                len <= cap,
+                "Invariant violated: length {} exceeds the capacity {}",
+                len,
+                cap
+            );
+            assert!(
+                ptr as usize % mem::align_of::<T>() == 0,
+                "Invariant violated: misaligned buffer"
+            );
+
+            if self.spilled {
+                let (heap_ptr, heap_len) = self.data.heap;
+                assert!(
+                    self.capacity > N,
+                    "Invariant violated: heap capacity {} doesn't exceed N",
+                    self.capacity
+                );
+                assert!(
+                    ptr == heap_ptr && len == heap_len && cap == self.capacity,
+                    "Invariant violated: spilled buffer isn't read from the heap parts"
+                );
+                assert!(!ptr.is_null(), "Invariant violated: null heap buffer");
+                assert!(
+                    invariants::heap_layout::<T>(cap).is_ok(),
+                    "Invariant violated: invalid heap capacity {}",
+                    cap
+                );
+                invariants::check_canaries(ptr, cap);
+            } else {
+                assert!(
+                    ptr == self.data.stack() && len == self.capacity && cap == N,
+                    "Invariant violated: inline buffer isn't read from the inline storage"
+                );
+            }
+        }
+    }
+
+    // Fills the slots between the length and `end` with the poison pattern in
+    // debug-invariants mode, after the items in them were moved out or dropped
+    #[inline(always)]
+    pub(crate) unsafe fn poison_spare(&mut self, end: usize) {
+        let (ptr, len, _) = self.ptr_mut();
+        let len = *len;
+        if end > len {
+            invariants::poison(ptr.add(len), end - len);
+        }
+    }
+
+    // NOTE: Evaluated when a constructor is instantiated, so an invalid inline
+    // capacity is a compile time error rather than a runtime check. The byte
+    // limit is usize::MAX unless it's set, which clippy flags as always true.
+    #[allow(clippy::absurd_extreme_comparisons)]
+    const VALID_INLINE_CAPACITY: () = {
+        assert!(N > 0, "Zero length buffer is not supported");
+        assert!(N < isize::MAX as usize, "Maximum length is isize::MAX");
+        assert!(
+            mem::size_of::<[T; N]>() <= MAX_INLINE_BYTES,
+            "The inline storage exceeds FASTVEC_MAX_INLINE_BYTES"
+        );
+    };
+
+    // NOTE: Zero-sized types are never moved to the heap, their length is
+    // stored in `capacity` just like for inline items, but it's not bounded
+    // by N and the capacity is reported as usize::MAX
+    const IS_ZST: bool = mem::size_of::<T>() == 0;
+
     #[inline(always)]
     pub(crate) unsafe fn ptr(&self) -> (*const T, usize, usize) {
-        if self.capacity > N {
+        if Self::IS_ZST {
+            (NonNull::dangling().as_ptr(), self.capacity, usize::MAX)
+        } else if self.capacity > N {
             let (ptr, len) = self.data.heap;
             (ptr, len, self.capacity)
         } else {
@@ -183,37 +1667,162 @@ where
 
     #[inline(always)]
     pub(crate) unsafe fn ptr_mut(&mut self) -> (*mut T, &mut usize, usize) {
-        if self.capacity > N {
+        #[cfg(feature = "debug-invariants")]
+        if !self.poisoned {
+            self.poisoned = true;
+            if !Self::IS_ZST && self.capacity <= N {
+                let len = self.capacity;
+                invariants::poison(self.data.stack_mut().add(len), N - len);
+            }
+        }
+
+        if Self::IS_ZST {
+            (NonNull::dangling().as_ptr(), &mut self.capacity, usize::MAX)
+        } else if self.capacity > N {
             let (ptr, ref mut len) = self.data.heap;
             (ptr, len, self.capacity)
         } else {
             (self.data.stack_mut(), &mut self.capacity, N)
         }
     }
+}
 
-    #[inline(always)]
-    // NOTE: This might be worth optimizing with another heuristics
-    fn grow_by(&self) -> usize {
-        self.capacity
+// NOTE: Evaluated when FastVec::from_array_in is instantiated, so an array
+// longer than the inline storage is a compile time error, just like an invalid
+// inline capacity
+struct ArrayFits<const M: usize, const N: usize>;
+
+impl<const M: usize, const N: usize> ArrayFits<M, N> {
+    const VALID: () = assert!(M <= N, "The array doesn't fit in the inline storage");
+}
+
+// Resolves the range bounds into a concrete range over `len` items, panicking
+// with the same messages as the slice indexing functions on invalid ranges
+pub(crate) fn slice_range<R>(range: R, len: usize) -> Range<usize>
+where
+    R: RangeBounds<usize>,
+{
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start
+            .checked_add(1)
+            .unwrap_or_else(|| panic!("attempted to index slice from after maximum usize")),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end
+            .checked_add(1)
+            .unwrap_or_else(|| panic!("attempted to index slice up to maximum usize")),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+
+    if start > end {
+        panic!("slice index starts at {} but ends at {}", start, end);
+    }
+    if end > len {
+        panic!(
+            "range end index {} out of range for slice of length {}",
+            end, len
+        );
+    }
+
+    start..end
+}
+
+// The maximum size of the inline storage in bytes, set by the
+// FASTVEC_MAX_INLINE_BYTES environment variable at build time. Unlimited by
+// default.
+const MAX_INLINE_BYTES: usize = match option_env!("FASTVEC_MAX_INLINE_BYTES") {
+    Some(bytes) => parse_usize(bytes),
+    None => usize::MAX,
+};
+
+// Parses a decimal number at compile time
+const fn parse_usize(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    assert!(!bytes.is_empty(), "FASTVEC_MAX_INLINE_BYTES is empty");
+
+    let mut value: usize = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let digit = bytes[i];
+        assert!(
+            digit.is_ascii_digit(),
+            "FASTVEC_MAX_INLINE_BYTES is not a decimal number"
+        );
+        value = match value.checked_mul(10) {
+            Some(value) => match value.checked_add((digit - b'0') as usize) {
+                Some(value) => value,
+                None => panic!("FASTVEC_MAX_INLINE_BYTES is too large"),
+            },
+            None => panic!("FASTVEC_MAX_INLINE_BYTES is too large"),
+        };
+        i += 1;
+    }
+
+    value
+}
+
+#[cold]
+#[inline(never)]
+fn capacity_overflow() -> ! {
+    panic!("capacity overflow");
+}
+
+// Panics on capacity overflow and calls the allocation error handler on OOM
+#[cold]
+#[inline(never)]
+fn abort_on_error(error: Error) -> ! {
+    match error {
+        Error::AllocError { layout, .. } => alloc_error(layout),
+        _ => capacity_overflow(),
     }
 }
 
-impl<T, const N: usize> Drop for FastVec<T, N>
+// Reports an allocation failure to the global allocation error handler, or
+// panics if it's not available
+#[cold]
+#[inline(never)]
+fn alloc_error(layout: Layout) -> ! {
+    #[cfg(feature = "alloc")]
+    handle_alloc_error(layout);
+
+    #[cfg(not(feature = "alloc"))]
+    panic!("memory allocation of {} bytes failed", layout.size());
+}
+
+impl<T, const N: usize, G, A> Drop for FastVec<T, N, G, A>
 where
-    T: Unpin,
+    G: GrowthPolicy,
+    A: Allocator,
 {
     fn drop(&mut self) {
-        unsafe {
-            let (ptr, len, cap) = self.ptr_mut();
-            for idx in 0..*len {
-                ptr::drop_in_place(ptr.add(idx));
-            }
-            if cap > N {
-                // NOTE: This is the current layout, this shouldn't fail.
-                // Unfortunately we don't have a way to gracefully report
-                // errors from drop.
-                let layout = Layout::array::<T>(cap).unwrap();
-                dealloc(ptr as *mut u8, layout);
+        self.check_invariants();
+
+        // Frees the heap buffer even if dropping one of the items panics
+        struct DeallocOnDrop<'a, T, const N: usize, G: GrowthPolicy, A: Allocator>(
+            &'a mut FastVec<T, N, G, A>,
+        );
+
+        impl<'a, T, const N: usize, G: GrowthPolicy, A: Allocator> Drop for DeallocOnDrop<'a, T, N, G, A> {
+            fn drop(&mut self) {
+                if self.0.is_heap_allocated() {
+                    unsafe {
+                        let (ptr, _, cap) = self.0.ptr_mut();
+                        self.0.deallocate_heap(ptr, cap);
+                    }
+                }
+            }
+        }
+
+        let guard = DeallocOnDrop(self);
+        if mem::needs_drop::<T>() {
+            // NOTE: Dropping the items as a slice keeps dropping the rest of
+            // them if one of the destructors panics
+            unsafe {
+                let (ptr, len, _) = guard.0.ptr_mut();
+                ptr::drop_in_place(ptr::slice_from_raw_parts_mut(ptr, *len));
             }
         }
     }
@@ -0,0 +1,111 @@
+use core::{marker::PhantomData, mem};
+
+/// Decides how much the capacity of a [`crate::FastVec`] grows when it runs
+/// out of space. The policy is a type parameter of the buffer, so it doesn't
+/// take up any space and the calls are resolved at compile time.
+///
+/// The returned capacities are only hints, the buffer always allocates room
+/// for at least the required number of items. Exact reservations (e.g.
+/// `reserve_exact()` or `with_capacity()`) bypass the policy.
+pub trait GrowthPolicy {
+    /// Returns the capacity of the first heap buffer, when the inline storage
+    /// of `inline` items runs out and `required` items need to be stored
+    fn spill_capacity<T>(inline: usize, required: usize) -> usize;
+
+    /// Returns the new capacity of the heap buffer of `cap` items, when
+    /// `required` items need to be stored
+    fn grow_capacity<T>(cap: usize, required: usize) -> usize;
+}
+
+/// Doubles the capacity every time the buffer runs out of space. This is the
+/// default policy and trades memory for the fewest number of reallocations.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Doubling;
+
+impl GrowthPolicy for Doubling {
+    #[inline]
+    fn spill_capacity<T>(inline: usize, required: usize) -> usize {
+        required.max(inline.saturating_mul(2))
+    }
+
+    #[inline]
+    fn grow_capacity<T>(cap: usize, required: usize) -> usize {
+        required.max(cap.saturating_mul(2))
+    }
+}
+
+/// Grows the capacity by 50% every time the buffer runs out of space, which
+/// wastes less memory than [`Doubling`] at the cost of more reallocations.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OneAndHalf;
+
+impl GrowthPolicy for OneAndHalf {
+    #[inline]
+    fn spill_capacity<T>(inline: usize, required: usize) -> usize {
+        required.max(inline.saturating_add(inline / 2))
+    }
+
+    #[inline]
+    fn grow_capacity<T>(cap: usize, required: usize) -> usize {
+        required.max(cap.saturating_add(cap / 2))
+    }
+}
+
+/// Grows the capacity by a fixed number of `STEP` items every time the buffer
+/// runs out of space. Useful for memory constrained workloads where the
+/// maximum size is roughly known.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FixedIncrement<const STEP: usize>;
+
+impl<const STEP: usize> GrowthPolicy for FixedIncrement<STEP> {
+    #[inline]
+    fn spill_capacity<T>(inline: usize, required: usize) -> usize {
+        required.max(inline.saturating_add(STEP))
+    }
+
+    #[inline]
+    fn grow_capacity<T>(cap: usize, required: usize) -> usize {
+        required.max(cap.saturating_add(STEP))
+    }
+}
+
+/// Grows the capacity following the `P` policy, then rounds the size of the
+/// heap buffer up to a multiple of `PAGE_SIZE` bytes, so the whole pages
+/// handed out by the allocator can be used.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PageAligned<P = Doubling, const PAGE_SIZE: usize = 4096> {
+    policy: PhantomData<P>,
+}
+
+impl<P, const PAGE_SIZE: usize> PageAligned<P, PAGE_SIZE> {
+    #[inline]
+    fn round_up<T>(cap: usize) -> usize {
+        let size = mem::size_of::<T>();
+        if size == 0 || PAGE_SIZE == 0 {
+            return cap;
+        }
+
+        match cap.checked_mul(size) {
+            Some(bytes) => match bytes.checked_add(PAGE_SIZE - 1) {
+                Some(padded) => (padded / PAGE_SIZE * PAGE_SIZE) / size,
+                None => cap,
+            },
+            None => cap,
+        }
+    }
+}
+
+impl<P, const PAGE_SIZE: usize> GrowthPolicy for PageAligned<P, PAGE_SIZE>
+where
+    P: GrowthPolicy,
+{
+    #[inline]
+    fn spill_capacity<T>(inline: usize, required: usize) -> usize {
+        Self::round_up::<T>(P::spill_capacity::<T>(inline, required))
+    }
+
+    #[inline]
+    fn grow_capacity<T>(cap: usize, required: usize) -> usize {
+        Self::round_up::<T>(P::grow_capacity::<T>(cap, required))
+    }
+}
@@ -0,0 +1,17 @@
+use core::hash::{Hash, Hasher};
+
+use crate::{allocator::Allocator, growth::GrowthPolicy, FastVec};
+
+impl<T, const N: usize, G, A> Hash for FastVec<T, N, G, A>
+where
+    T: Hash,
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    /// Hashes the items exactly like the `[T]` slice does, so the buffer can
+    /// be looked up by slice in hash based collections
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&self[..], state);
+    }
+}
@@ -1,7 +1,12 @@
-use crate::FastVec;
-use std::{ops::{Index, IndexMut}, slice::SliceIndex};
+use crate::{allocator::Allocator, growth::GrowthPolicy, FastVec};
+use core::{
+    ops::{Index, IndexMut},
+    slice::SliceIndex,
+};
 
-impl<T: Unpin, I: SliceIndex<[T]>, const N: usize> Index<I> for FastVec<T, N> {
+impl<T, I: SliceIndex<[T]>, const N: usize, G: GrowthPolicy, A: Allocator> Index<I>
+    for FastVec<T, N, G, A>
+{
     type Output = I::Output;
 
     fn index(&self, index: I) -> &Self::Output {
@@ -9,8 +14,10 @@ impl<T: Unpin, I: SliceIndex<[T]>, const N: usize> Index<I> for FastVec<T, N> {
     }
 }
 
-impl<T: Unpin, I: SliceIndex<[T]>, const N: usize> IndexMut<I> for FastVec<T, N> {
+impl<T, I: SliceIndex<[T]>, const N: usize, G: GrowthPolicy, A: Allocator> IndexMut<I>
+    for FastVec<T, N, G, A>
+{
     fn index_mut(&mut self, index: I) -> &mut Self::Output {
         IndexMut::index_mut(&mut **self, index)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,193 @@
+use core::{fmt, iter::FusedIterator, ptr, slice};
+
+use crate::{
+    allocator::{Allocator, Global},
+    growth::{Doubling, GrowthPolicy},
+    invariants, FastVec,
+};
+
+/// An iterator which moves the items out of a buffer. It is created by the
+/// [`IntoIterator`] implementation of [`FastVec`].
+///
+/// The iterator takes over the buffer as is, so the inline items stay inline
+/// and the heap buffer is reused without allocating.
+pub struct IntoIter<T, const N: usize, G = Doubling, A = Global>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    // NOTE: The length of the buffer is zero, so it only frees the heap
+    // buffer when dropped and the remaining items are dropped by the iterator
+    vec: FastVec<T, N, G, A>,
+    // The index of the next item from the front
+    start: usize,
+    // The index after the next item from the back
+    end: usize,
+}
+
+impl<T, const N: usize, G, A> IntoIter<T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    /// Returns the remaining items as a slice
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        unsafe {
+            let (ptr, _, _) = self.vec.ptr();
+            slice::from_raw_parts(ptr.add(self.start), self.end - self.start)
+        }
+    }
+
+    /// Returns the remaining items as a mutable slice
+    #[must_use]
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe {
+            let (ptr, _, _) = self.vec.ptr_mut();
+            slice::from_raw_parts_mut(ptr.add(self.start), self.end - self.start)
+        }
+    }
+}
+
+impl<T, const N: usize, G, A> IntoIterator for FastVec<T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    type Item = T;
+    type IntoIter = IntoIter<T, N, G, A>;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        unsafe {
+            let (_, len, _) = self.ptr_mut();
+            let end = *len;
+            *len = 0;
+
+            IntoIter {
+                vec: self,
+                start: 0,
+                end,
+            }
+        }
+    }
+}
+
+impl<'a, T, const N: usize, G, A> IntoIterator for &'a FastVec<T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, const N: usize, G, A> IntoIterator for &'a mut FastVec<T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    type Item = &'a mut T;
+    type IntoIter = slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T, const N: usize, G, A> Iterator for IntoIter<T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None;
+        }
+
+        unsafe {
+            let (ptr, _, _) = self.vec.ptr_mut();
+            let value = ptr::read(ptr.add(self.start));
+            invariants::poison(ptr.add(self.start), 1);
+            self.start += 1;
+            Some(value)
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start;
+        (len, Some(len))
+    }
+
+    #[inline]
+    fn count(self) -> usize {
+        self.len()
+    }
+}
+
+impl<T, const N: usize, G, A> DoubleEndedIterator for IntoIter<T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None;
+        }
+
+        unsafe {
+            let (ptr, _, _) = self.vec.ptr_mut();
+            self.end -= 1;
+            let value = ptr::read(ptr.add(self.end));
+            invariants::poison(ptr.add(self.end), 1);
+            Some(value)
+        }
+    }
+}
+
+impl<T, const N: usize, G, A> ExactSizeIterator for IntoIter<T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+}
+
+impl<T, const N: usize, G, A> FusedIterator for IntoIter<T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+}
+
+impl<T, const N: usize, G, A> fmt::Debug for IntoIter<T, N, G, A>
+where
+    T: fmt::Debug,
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("IntoIter").field(&self.as_slice()).finish()
+    }
+}
+
+impl<T, const N: usize, G, A> Drop for IntoIter<T, N, G, A>
+where
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    fn drop(&mut self) {
+        // NOTE: The buffer itself is dropped afterwards, freeing the heap
+        // allocation even if dropping one of the items panics
+        unsafe {
+            ptr::drop_in_place(self.as_mut_slice());
+        }
+    }
+}
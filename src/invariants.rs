@@ -0,0 +1,88 @@
+use core::alloc::{Layout, LayoutError};
+#[cfg(feature = "debug-invariants")]
+use core::{ptr, slice};
+
+// Byte pattern written over the popped and uninitialized slots
+#[cfg(feature = "debug-invariants")]
+pub(crate) const POISON: u8 = 0xA5;
+
+// Byte pattern of the guard areas before and after the heap buffer
+#[cfg(feature = "debug-invariants")]
+pub(crate) const CANARY: u8 = 0xFD;
+
+// Size of the guard area after the heap buffer, the one before it is padded
+// to the alignment of the items
+#[cfg(feature = "debug-invariants")]
+const CANARY_LEN: usize = 16;
+
+// Returns the layout of a heap buffer for `cap` items and the offset of the
+// first item in it. In debug-invariants mode the items are surrounded by the
+// guard areas, otherwise it's just the array of items. The raw parts API hands
+// out the array of items alone, see FastVec::attach_guard().
+pub(crate) fn heap_layout<T>(cap: usize) -> Result<(Layout, usize), LayoutError> {
+    let items = Layout::array::<T>(cap)?;
+
+    #[cfg(feature = "debug-invariants")]
+    {
+        let canary = Layout::array::<u8>(CANARY_LEN)?;
+        let (layout, offset) = canary.extend(items)?;
+        let (layout, _) = layout.extend(canary)?;
+        Ok((layout, offset))
+    }
+
+    #[cfg(not(feature = "debug-invariants"))]
+    Ok((items, 0))
+}
+
+// Fills the guard areas around the heap buffer of `cap` items starting at
+// `items`
+#[inline(always)]
+pub(crate) unsafe fn write_canaries<T>(items: *mut T, cap: usize) {
+    #[cfg(feature = "debug-invariants")]
+    {
+        let (layout, offset) = heap_layout::<T>(cap).unwrap();
+        let base = (items as *mut u8).sub(offset);
+        let end = offset + cap * core::mem::size_of::<T>();
+        ptr::write_bytes(base, CANARY, offset);
+        ptr::write_bytes(base.add(end), CANARY, layout.size() - end);
+    }
+
+    #[cfg(not(feature = "debug-invariants"))]
+    let _ = (items, cap);
+}
+
+// Panics if the guard areas around the heap buffer of `cap` items starting at
+// `items` were overwritten
+#[inline(always)]
+pub(crate) unsafe fn check_canaries<T>(items: *const T, cap: usize) {
+    #[cfg(feature = "debug-invariants")]
+    {
+        let (layout, offset) = heap_layout::<T>(cap).unwrap();
+        let base = (items as *const u8).sub(offset);
+        let end = offset + cap * core::mem::size_of::<T>();
+        let prefix = slice::from_raw_parts(base, offset);
+        let suffix = slice::from_raw_parts(base.add(end), layout.size() - end);
+        assert!(
+            prefix.iter().all(|&byte| byte == CANARY),
+            "Invariant violated: heap buffer underflow detected"
+        );
+        assert!(
+            suffix.iter().all(|&byte| byte == CANARY),
+            "Invariant violated: heap buffer overflow detected"
+        );
+    }
+
+    #[cfg(not(feature = "debug-invariants"))]
+    let _ = (items, cap);
+}
+
+// Fills `count` slots starting at `ptr` with the poison pattern, so reads of
+// stale or uninitialized items are easy to spot
+#[inline(always)]
+pub(crate) unsafe fn poison<T>(ptr: *mut T, count: usize) {
+    #[cfg(feature = "debug-invariants")]
+    ptr::write_bytes(ptr, POISON, count);
+
+    #[cfg(not(feature = "debug-invariants"))]
+    let _ = (ptr, count);
+}
@@ -1,16 +1,93 @@
+//! A smallvec-like simple and performant buffer implementation, which stores
+//! a fixed number of items inline and moves them to the heap when it runs out
+//! of space.
+//!
+//! The crate is `no_std` compatible. The `std` feature (enabled by default)
+//! implements `std::error::Error` for the error type, the `alloc` feature
+//! (enabled by `std`) provides the global allocator for the heap buffer.
+//! Without the `alloc` feature [`Global`] fails every allocation, so running
+//! out of the inline storage is reported as a capacity overflow, and the
+//! buffers can only spill to the heap through a custom [`Allocator`].
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(missing_docs)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 /// Contains the error type and all error variations this crate can return
 pub mod error;
 
+/// The allocator interface used for the heap buffer and the global allocator
+pub mod allocator;
+
 /// This is the core functinoality, containing the FastVec structure and
 /// implementation
 pub mod fastvec;
+
+/// Slice access to the items via `Deref` and `DerefMut`
 pub mod deref;
+
+/// Indexing into the items via `Index` and `IndexMut`
 pub mod index;
 
-pub use crate::fastvec::FastVec;
+/// The iterator returned by `FastVec::extract_if`
+pub mod extract_if;
+
+/// The iterator returned by `FastVec::drain`
+pub mod drain;
+
+/// The iterator returned by `FastVec::splice`
+pub mod splice;
+
+/// Iteration over the items via `IntoIterator`
+pub mod into_iter;
+
+/// Building buffers from iterators via `Extend` and `FromIterator`
+pub mod extend;
+
+/// The growth policies deciding how the capacity grows
+pub mod growth;
+
+/// Cloning buffers via `Clone`
+pub mod clone;
+
+/// Formatting the items via `Debug`
+pub mod debug;
+
+/// Creating empty buffers via `Default`
+pub mod default;
+
+/// Borrowing the items as slices via `AsRef`, `AsMut`, `Borrow` and
+/// `BorrowMut`
+pub mod borrow;
+
+/// Converting from and to vectors, arrays and slices via `From`
+pub mod convert;
+
+/// Comparing buffers via `PartialEq`, `Eq`, `PartialOrd` and `Ord`
+pub mod cmp;
+
+/// Hashing buffers via `Hash`
+pub mod hash;
+
+/// Heap buffer guard areas, poisoning and internal state validation for the
+/// `debug-invariants` feature
+mod invariants;
+
+pub use crate::{
+    allocator::{Allocator, Global},
+    drain::Drain,
+    extract_if::ExtractIf,
+    fastvec::FastVec,
+    growth::GrowthPolicy,
+    into_iter::IntoIter,
+    splice::Splice,
+};
 
 /// This is a test
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests;
+
+/// Tests of the `no_std` builds, with and without the `alloc` feature
+#[cfg(all(test, not(feature = "std")))]
+mod tests_no_std;
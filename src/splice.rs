@@ -0,0 +1,128 @@
+use core::{iter::FusedIterator, ptr};
+
+use crate::{
+    allocator::{Allocator, Global},
+    drain::Drain,
+    error::Operation,
+    growth::{Doubling, GrowthPolicy},
+};
+
+/// A splicing iterator which replaces a range of items with the items of
+/// another iterator, and yields the removed items. It is created by
+/// [`crate::FastVec::splice`].
+///
+/// The replacement happens when this iterator is dropped, so leaking it leaves
+/// the buffer cut at the start of the range, just like leaking a [`Drain`].
+pub struct Splice<'a, I, const N: usize, G = Doubling, A = Global>
+where
+    I: Iterator + 'a,
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    pub(crate) drain: Drain<'a, I::Item, N, G, A>,
+    pub(crate) replace_with: I,
+}
+
+impl<'a, I, const N: usize, G, A> Iterator for Splice<'a, I, N, G, A>
+where
+    I: Iterator + 'a,
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.drain.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.drain.size_hint()
+    }
+}
+
+impl<'a, I, const N: usize, G, A> DoubleEndedIterator for Splice<'a, I, N, G, A>
+where
+    I: Iterator + 'a,
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.drain.next_back()
+    }
+}
+
+impl<'a, I, const N: usize, G, A> ExactSizeIterator for Splice<'a, I, N, G, A>
+where
+    I: Iterator + 'a,
+    G: GrowthPolicy,
+    A: Allocator,
+{
+}
+
+impl<'a, I, const N: usize, G, A> FusedIterator for Splice<'a, I, N, G, A>
+where
+    I: Iterator + 'a,
+    G: GrowthPolicy,
+    A: Allocator,
+{
+}
+
+impl<'a, I, const N: usize, G, A> Drop for Splice<'a, I, N, G, A>
+where
+    I: Iterator + 'a,
+    G: GrowthPolicy,
+    A: Allocator,
+{
+    fn drop(&mut self) {
+        self.drain.by_ref().for_each(drop);
+        // NOTE: The drained range is now empty, so there's nothing left for
+        // the Drain to drop, only the tail to move back in place
+        self.drain.iter = [].iter();
+
+        unsafe {
+            if self.drain.tail_len == 0 {
+                self.drain
+                    .vec
+                    .as_mut()
+                    .extend_iter(self.replace_with.by_ref(), Operation::Splice);
+                return;
+            }
+
+            // First fill the range left by the drained items
+            if !self.drain.fill(&mut self.replace_with) {
+                return;
+            }
+
+            // If there are more items, try to use the lower bound of the size
+            // hint to make room for them with a single move
+            let (lower_bound, _) = self.replace_with.size_hint();
+            if lower_bound > 0 {
+                self.drain.move_tail(lower_bound);
+                if !self.drain.fill(&mut self.replace_with) {
+                    return;
+                }
+            }
+
+            // Make room for the rest in doubling chunks, so the tail is moved
+            // a logarithmic number of times without collecting the items
+            let mut room = lower_bound;
+            while let Some(item) = self.replace_with.next() {
+                let (lower_bound, _) = self.replace_with.size_hint();
+                room = lower_bound.saturating_add(1).max(room.saturating_mul(2));
+                self.drain.move_tail(room);
+
+                let (ptr, len, _) = self.drain.vec.as_mut().ptr_mut();
+                ptr::write(ptr.add(*len), item);
+                *len += 1;
+
+                if !self.drain.fill(&mut self.replace_with) {
+                    return;
+                }
+            }
+        }
+        // The Drain moves the tail back in place when it is dropped
+    }
+}
@@ -1,6 +1,18 @@
-use std::{mem, sync::RwLock};
+use std::{
+    alloc::Layout,
+    cell::Cell,
+    mem,
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr::NonNull,
+    sync::RwLock,
+};
 
-use super::fastvec::FastVec;
+use super::{
+    allocator::{AllocError, Allocator, Global},
+    error::{Error, ErrorKind, Operation},
+    fastvec::FastVec,
+    growth::Doubling,
+};
 
 #[test]
 fn can_store_retrieve_drop() {
@@ -43,6 +55,7 @@ fn can_store_retrieve_drop() {
 
 #[test]
 fn large_amount_of_items_push_pop() {
+    #[allow(dead_code)]
     struct Test(usize, &'static str);
 
     let mut buf = FastVec::<Test, 3>::new();
@@ -69,7 +82,7 @@ fn large_amount_of_items_push_pop() {
     buf.push(Test(21, "X"));
     buf.push(Test(22, "Y"));
     buf.push(Test(23, "Z"));
-    
+
     assert!(buf.pop().is_some());
     assert!(buf.pop().is_some());
     assert!(buf.pop().is_some());
@@ -96,4 +109,1278 @@ fn large_amount_of_items_push_pop() {
 
     assert!(buf.pop().is_none());
     assert!(buf.pop().is_none());
-}
\ No newline at end of file
+}
+
+#[test]
+fn fallible_allocation() {
+    let mut buf = FastVec::<u32, 2>::try_with_capacity(5).unwrap();
+    assert!(buf.is_heap_allocated());
+    assert_eq!(buf.cap(), 5);
+
+    buf.try_push(1).unwrap();
+    buf.try_extend_from_slice(&[2, 3, 4, 5, 6]).unwrap();
+    assert_eq!(&*buf, &[1, 2, 3, 4, 5, 6]);
+    assert!(buf.cap() >= 6);
+
+    buf.try_reserve_exact(4).unwrap();
+    assert_eq!(buf.cap(), 10);
+
+    assert_eq!(
+        buf.try_reserve(usize::MAX),
+        Err(Error::CapacityOverflow {
+            operation: Operation::Reserve,
+            capacity: 10
+        })
+    );
+    let err = buf.try_reserve_exact(usize::MAX / 2).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::LayoutError);
+    assert_eq!(err.operation(), Operation::ReserveExact);
+    assert_eq!(err.capacity(), 10);
+    assert!(std::error::Error::source(&err).is_some());
+    let err = FastVec::<u32, 2>::try_with_capacity(usize::MAX)
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), ErrorKind::LayoutError);
+    assert_eq!(err.operation(), Operation::WithCapacity);
+    assert_eq!(err.capacity(), 2);
+
+    // A failed reservation leaves the buffer untouched
+    assert_eq!(buf.cap(), 10);
+    assert_eq!(&*buf, &[1, 2, 3, 4, 5, 6]);
+}
+
+#[test]
+fn positional_editing() {
+    let mut buf = FastVec::<u32, 4>::new();
+    buf.insert(0, 2);
+    buf.insert(0, 0);
+    buf.insert(1, 1);
+    buf.insert(3, 4);
+    assert!(!buf.is_heap_allocated());
+    assert_eq!(&*buf, &[0, 1, 2, 4]);
+
+    // Spills to the heap
+    buf.insert(3, 3);
+    assert!(buf.is_heap_allocated());
+    assert_eq!(&*buf, &[0, 1, 2, 3, 4]);
+
+    assert_eq!(buf.remove(0), 0);
+    assert_eq!(buf.remove(3), 4);
+    assert_eq!(&*buf, &[1, 2, 3]);
+
+    buf.push(4);
+    assert_eq!(buf.swap_remove(0), 1);
+    assert_eq!(&*buf, &[4, 2, 3]);
+    assert_eq!(buf.swap_remove(2), 3);
+    assert_eq!(&*buf, &[4, 2]);
+
+    buf.truncate(5);
+    assert_eq!(buf.len(), 2);
+    buf.truncate(1);
+    assert_eq!(&*buf, &[4]);
+
+    let cap = buf.cap();
+    buf.clear();
+    assert!(buf.is_empty());
+    assert_eq!(buf.cap(), cap);
+
+    let mut buf = FastVec::<u32, 4>::new();
+    buf.insert(0, 1);
+    assert_eq!(buf.swap_remove(0), 1);
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn truncate_drops_items() {
+    static DROPTEST: RwLock<u8> = RwLock::new(0);
+
+    struct Test(#[allow(dead_code)] u8);
+
+    impl Drop for Test {
+        fn drop(&mut self) {
+            if let Ok(mut write_guard) = DROPTEST.write() {
+                *write_guard += 1;
+            }
+        }
+    }
+
+    let mut buf = FastVec::<Test, 2>::new();
+    buf.push(Test(0));
+    buf.push(Test(0));
+    buf.push(Test(0));
+    buf.push(Test(0));
+    buf.truncate(1);
+    assert!(DROPTEST.read().unwrap().eq(&3));
+
+    mem::drop(buf);
+    assert!(DROPTEST.read().unwrap().eq(&4));
+}
+
+#[test]
+#[should_panic(expected = "insertion index (is 2) should be <= len (is 1)")]
+fn insert_out_of_bounds() {
+    let mut buf = FastVec::<u32, 2>::new();
+    buf.push(1);
+    buf.insert(2, 2);
+}
+
+#[test]
+#[should_panic(expected = "removal index (is 1) should be < len (is 1)")]
+fn remove_out_of_bounds() {
+    let mut buf = FastVec::<u32, 2>::new();
+    buf.push(1);
+    buf.remove(1);
+}
+
+// Counts how many times the items are dropped to catch leaks and double drops
+struct DropCounter<'a>(u32, &'a Cell<usize>);
+
+impl<'a> Drop for DropCounter<'a> {
+    fn drop(&mut self) {
+        self.1.set(self.1.get() + 1);
+    }
+}
+
+#[test]
+fn retain_and_dedup() {
+    let mut buf = FastVec::<u32, 4>::new();
+    buf.try_extend_from_slice(&[1, 2, 3, 4]).unwrap();
+    buf.retain(|&x| x % 2 == 0);
+    assert_eq!(&*buf, &[2, 4]);
+
+    buf.try_extend_from_slice(&[5, 6, 7, 8, 9]).unwrap();
+    assert!(buf.is_heap_allocated());
+    buf.retain_mut(|x| {
+        *x *= 10;
+        *x > 50
+    });
+    assert_eq!(&*buf, &[60, 70, 80, 90]);
+
+    let mut buf = FastVec::<u32, 4>::new();
+    buf.try_extend_from_slice(&[1, 1, 2, 2, 2, 3, 1, 1])
+        .unwrap();
+    buf.dedup();
+    assert_eq!(&*buf, &[1, 2, 3, 1]);
+
+    let mut buf = FastVec::<u32, 4>::new();
+    buf.try_extend_from_slice(&[10, 11, 20, 21, 22, 30])
+        .unwrap();
+    buf.dedup_by_key(|x| *x / 10);
+    assert_eq!(&*buf, &[10, 20, 30]);
+
+    let mut buf = FastVec::<u32, 4>::new();
+    buf.try_extend_from_slice(&[1, 2, 4, 5, 7]).unwrap();
+    buf.dedup_by(|a, b| *a == *b + 1);
+    assert_eq!(&*buf, &[1, 4, 7]);
+}
+
+#[test]
+fn extract_if() {
+    let mut buf = FastVec::<u32, 4>::new();
+    buf.try_extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8])
+        .unwrap();
+
+    let mut extracted = buf.extract_if(.., |x| *x % 2 == 0);
+    assert_eq!(extracted.next(), Some(2));
+    assert_eq!(extracted.next(), Some(4));
+    mem::drop(extracted);
+    assert_eq!(&*buf, &[1, 3, 5, 6, 7, 8]);
+
+    let mut extracted = FastVec::<u32, 4>::new();
+    for x in buf.extract_if(1..5, |x| *x > 4) {
+        extracted.push(x);
+    }
+    assert_eq!(&*extracted, &[5, 6, 7]);
+    assert_eq!(&*buf, &[1, 3, 8]);
+
+    // Leaking the iterator leaks items but leaves a valid buffer
+    mem::forget(buf.extract_if(.., |_| true));
+    assert!(buf.is_empty());
+    buf.push(1);
+    assert_eq!(&*buf, &[1]);
+}
+
+#[test]
+fn retain_dedup_panic_safety() {
+    let drops = Cell::new(0);
+
+    let mut buf = FastVec::<DropCounter, 2>::new();
+    for i in 0..6 {
+        buf.push(DropCounter(i, &drops));
+    }
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        buf.retain(|item| {
+            if item.0 == 3 {
+                panic!("retain");
+            }
+            item.0 != 1
+        })
+    }));
+    assert!(result.is_err());
+    assert_eq!(drops.get(), 1);
+    assert_eq!(
+        buf.iter().map(|item| item.0).collect::<Vec<_>>(),
+        vec![0, 2, 3, 4, 5]
+    );
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        buf.dedup_by(|a, _| {
+            if a.0 == 4 {
+                panic!("dedup");
+            }
+            a.0 == 2
+        })
+    }));
+    assert!(result.is_err());
+    assert_eq!(drops.get(), 2);
+    assert_eq!(
+        buf.iter().map(|item| item.0).collect::<Vec<_>>(),
+        vec![0, 3, 4, 5]
+    );
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        buf.extract_if(.., |item| {
+            if item.0 == 4 {
+                panic!("extract_if");
+            }
+            true
+        })
+        .for_each(mem::drop)
+    }));
+    assert!(result.is_err());
+    assert_eq!(drops.get(), 4);
+    assert_eq!(
+        buf.iter().map(|item| item.0).collect::<Vec<_>>(),
+        vec![4, 5]
+    );
+
+    mem::drop(buf);
+    assert_eq!(drops.get(), 6);
+}
+
+#[test]
+fn drain() {
+    let mut buf = FastVec::<u32, 8>::new();
+    buf.try_extend_from_slice(&[1, 2, 3, 4, 5, 6]).unwrap();
+
+    let mut drain = buf.drain(1..5);
+    assert_eq!(drain.len(), 4);
+    assert_eq!(drain.next(), Some(2));
+    assert_eq!(drain.next_back(), Some(5));
+    assert_eq!(drain.as_slice(), &[3, 4]);
+    mem::drop(drain);
+    assert_eq!(&*buf, &[1, 6]);
+
+    buf.try_extend_from_slice(&[7, 8, 9, 10, 11, 12, 13])
+        .unwrap();
+    assert!(buf.is_heap_allocated());
+    let mut drained = FastVec::<u32, 2>::new();
+    for x in buf.drain(..=2) {
+        drained.push(x);
+    }
+    assert_eq!(&*drained, &[1, 6, 7]);
+    assert_eq!(&*buf, &[8, 9, 10, 11, 12, 13]);
+
+    // Leaking the iterator cuts the buffer at the start of the range
+    mem::forget(buf.drain(2..4));
+    assert_eq!(&*buf, &[8, 9]);
+
+    buf.drain(..);
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn drain_drops_items() {
+    let drops = Cell::new(0);
+
+    let mut buf = FastVec::<DropCounter, 2>::new();
+    for i in 0..5 {
+        buf.push(DropCounter(i, &drops));
+    }
+    let mut drain = buf.drain(1..4);
+    mem::drop(drain.next());
+    assert_eq!(drops.get(), 1);
+    mem::drop(drain);
+    assert_eq!(drops.get(), 3);
+    assert_eq!(
+        buf.iter().map(|item| item.0).collect::<Vec<_>>(),
+        vec![0, 4]
+    );
+
+    mem::drop(buf);
+    assert_eq!(drops.get(), 5);
+}
+
+#[test]
+fn splice() {
+    let mut buf = FastVec::<u32, 8>::new();
+    buf.try_extend_from_slice(&[1, 2, 3, 4, 5]).unwrap();
+
+    // Shorter replacement
+    let removed = buf.splice(1..4, [10]).collect::<Vec<_>>();
+    assert_eq!(removed, vec![2, 3, 4]);
+    assert_eq!(&*buf, &[1, 10, 5]);
+    assert!(!buf.is_heap_allocated());
+
+    // Longer replacement with an exact size hint, spilling to the heap
+    buf.splice(1..2, 20..28);
+    assert_eq!(&*buf, &[1, 20, 21, 22, 23, 24, 25, 26, 27, 5]);
+    assert!(buf.is_heap_allocated());
+
+    // Longer replacement without a size hint
+    buf.splice(..2, (30..34).filter(|_| true));
+    assert_eq!(&*buf, &[30, 31, 32, 33, 21, 22, 23, 24, 25, 26, 27, 5]);
+
+    // Replacement at the end
+    buf.splice(4.., [40, 41]);
+    assert_eq!(&*buf, &[30, 31, 32, 33, 40, 41]);
+
+    // Leaking the iterator cuts the buffer at the start of the range
+    mem::forget(buf.splice(2..4, [50, 51]));
+    assert_eq!(&*buf, &[30, 31]);
+
+    // Many items without a size hint, making room for them in chunks
+    buf.splice(..1, (0..100).filter(|_| true));
+    assert_eq!(buf.len(), 101);
+    assert_eq!(buf[..2], [0, 1]);
+    assert_eq!(buf[98..], [98, 99, 31]);
+}
+
+#[test]
+fn splice_inline_spill() {
+    let mut buf = FastVec::<String, 4>::new();
+    buf.push("a".to_string());
+    buf.push("b".to_string());
+    buf.push("c".to_string());
+
+    buf.splice(1..2, (0..3).map(|x| x.to_string()).filter(|_| true));
+    assert!(buf.is_heap_allocated());
+    assert_eq!(&*buf, &["a", "0", "1", "2", "c"]);
+}
+
+#[test]
+fn into_iter() {
+    let mut buf = FastVec::<u32, 4>::new();
+    buf.try_extend_from_slice(&[1, 2, 3]).unwrap();
+
+    for x in &mut buf {
+        *x *= 2;
+    }
+    assert_eq!((&buf).into_iter().sum::<u32>(), 12);
+
+    let mut iter = buf.into_iter();
+    assert_eq!(iter.len(), 3);
+    assert_eq!(iter.next(), Some(2));
+    iter.as_mut_slice()[1] = 7;
+    assert_eq!(iter.as_slice(), &[4, 7]);
+    assert_eq!(iter.next_back(), Some(7));
+    assert_eq!(iter.next_back(), Some(4));
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.next_back(), None);
+
+    let mut buf = FastVec::<String, 2>::new();
+    for x in 0..5 {
+        buf.push(x.to_string());
+    }
+    assert!(buf.is_heap_allocated());
+    assert_eq!(
+        buf.into_iter().rev().collect::<Vec<_>>(),
+        vec!["4", "3", "2", "1", "0"]
+    );
+}
+
+#[test]
+fn into_iter_drops_remaining_items() {
+    let drops = Cell::new(0);
+
+    for spill in [false, true] {
+        drops.set(0);
+        let mut buf = FastVec::<DropCounter, 4>::new();
+        let count = if spill { 6 } else { 4 };
+        for i in 0..count {
+            buf.push(DropCounter(i, &drops));
+        }
+
+        let mut iter = buf.into_iter();
+        assert_eq!(iter.next().map(|item| item.0), Some(0));
+        assert_eq!(iter.next_back().map(|item| item.0), Some(count - 1));
+        assert_eq!(drops.get(), 2);
+        mem::drop(iter);
+        assert_eq!(drops.get(), count as usize);
+    }
+}
+
+#[test]
+fn extend_and_collect() {
+    let buf = (0..4).collect::<FastVec<u32, 4>>();
+    assert!(!buf.is_heap_allocated());
+    assert_eq!(&*buf, &[0, 1, 2, 3]);
+
+    // A single allocation for an exact size hint
+    let buf = (0..10).collect::<FastVec<u32, 4>>();
+    assert!(buf.is_heap_allocated());
+    assert_eq!(buf.cap(), 10);
+
+    // Iterators without a size hint still grow as needed
+    let mut buf = (0..10).filter(|x| x % 2 == 0).collect::<FastVec<u32, 2>>();
+    assert_eq!(&*buf, &[0, 2, 4, 6, 8]);
+
+    buf.extend(&[10, 12]);
+    buf.extend((14..30).step_by(2).filter(|_| true));
+    assert_eq!(buf.len(), 15);
+    assert!(buf.iter().enumerate().all(|(i, &x)| x == i as u32 * 2));
+}
+
+#[test]
+fn extend_from_slice_and_within() {
+    let mut buf = FastVec::<u8, 8>::new();
+    buf.extend_from_copy_slice(b"abc");
+    buf.extend_from_within_copy(1..);
+    assert!(!buf.is_heap_allocated());
+    assert_eq!(&*buf, b"abcbc");
+
+    buf.extend_from_copy_slice(b"defgh");
+    assert!(buf.is_heap_allocated());
+    buf.extend_from_within_copy(..2);
+    assert_eq!(&*buf, b"abcbcdefghab");
+
+    let mut buf = FastVec::<String, 2>::new();
+    buf.extend_from_slice(&["a".to_string(), "b".to_string()]);
+    buf.extend_from_within(..);
+    buf.extend_from_within(1..=2);
+    assert_eq!(&*buf, &["a", "b", "a", "b", "b", "a"]);
+}
+
+#[test]
+fn capacity_management() {
+    let buf = FastVec::<u32, 4>::with_capacity(3);
+    assert!(!buf.is_heap_allocated());
+    assert_eq!(buf.cap(), 4);
+
+    let mut buf = FastVec::<u32, 4>::with_capacity(16);
+    assert!(buf.is_heap_allocated());
+    assert_eq!(buf.cap(), 16);
+
+    buf.extend(0..6);
+    buf.reserve(4);
+    assert_eq!(buf.cap(), 16);
+    buf.reserve_exact(20);
+    assert_eq!(buf.cap(), 26);
+    buf.reserve(30);
+    assert!(buf.cap() >= 36);
+
+    buf.shrink_to(10);
+    assert_eq!(buf.cap(), 10);
+    buf.shrink_to(20);
+    assert_eq!(buf.cap(), 10);
+    buf.shrink_to_fit();
+    assert_eq!(buf.cap(), 6);
+    assert!(buf.is_heap_allocated());
+
+    // Moves back to the inline storage once the items fit
+    buf.truncate(3);
+    buf.shrink_to(5);
+    assert!(buf.is_heap_allocated());
+    buf.shrink_to(4);
+    assert!(!buf.is_heap_allocated());
+    assert_eq!(buf.cap(), 4);
+    assert_eq!(&*buf, &[0, 1, 2]);
+
+    buf.extend(3..8);
+    assert!(buf.is_heap_allocated());
+    buf.clear();
+    buf.shrink_to_fit();
+    assert!(!buf.is_heap_allocated());
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn spill_and_inline() {
+    let live = Cell::new(0);
+    let mut buf = FastVec::<String, 4, Doubling, _>::new_in(TrackingAllocator(&live));
+    buf.push("a".to_string());
+    buf.push("b".to_string());
+
+    buf.spill();
+    assert!(buf.is_heap_allocated());
+    assert_eq!(buf.cap(), 8);
+    assert_eq!(live.get(), 1);
+    buf.try_spill().unwrap();
+    assert_eq!(live.get(), 1);
+
+    assert!(buf.try_inline());
+    assert!(!buf.is_heap_allocated());
+    assert_eq!(live.get(), 0);
+    assert_eq!(&*buf, &["a", "b"]);
+
+    buf.extend(["c", "d", "e"].map(String::from));
+    assert!(!buf.try_inline());
+    buf.pop();
+    assert!(buf.try_inline());
+    assert_eq!(&*buf, &["a", "b", "c", "d"]);
+
+    let mut zst = FastVec::<(), 2>::new();
+    zst.spill();
+    assert!(!zst.is_heap_allocated());
+
+    let mut failing = FastVec::<u32, 2, Doubling, _>::new_in(FailingAllocator);
+    failing.push(1);
+    assert_eq!(
+        failing.try_spill().unwrap_err().operation(),
+        Operation::Spill
+    );
+    assert_eq!(&*failing, &[1]);
+}
+
+#[test]
+fn into_inline_capacity() {
+    let live = Cell::new(0);
+    let mut buf = FastVec::<String, 2, Doubling, _>::new_in(TrackingAllocator(&live));
+    buf.extend(["a", "b", "c"].map(String::from));
+    let first = buf[0].as_ptr();
+
+    // The heap buffer is handed over if it's larger than the new inline storage
+    let buf = buf.into_inline_capacity::<3>();
+    assert!(buf.is_heap_allocated());
+    assert_eq!(live.get(), 1);
+    assert_eq!(buf[0].as_ptr(), first);
+
+    // The items are moved inline if they fit
+    let buf = buf.into_inline_capacity::<8>();
+    assert!(!buf.is_heap_allocated());
+    assert_eq!(live.get(), 0);
+    assert_eq!(&*buf, &["a", "b", "c"]);
+    assert_eq!(buf[0].as_ptr(), first);
+
+    // A smaller inline storage moves the items to the heap
+    let buf = buf.into_inline_capacity::<1>();
+    assert!(buf.is_heap_allocated());
+    assert_eq!(live.get(), 1);
+    assert_eq!(&*buf, &["a", "b", "c"]);
+
+    mem::drop(buf);
+    assert_eq!(live.get(), 0);
+
+    let zst = FastVec::<(), 4>::from_array([(), (), ()]);
+    assert_eq!(zst.into_inline_capacity::<1>().len(), 3);
+}
+
+#[test]
+fn comparisons() {
+    use std::{
+        collections::{hash_map::DefaultHasher, BTreeSet, HashSet},
+        hash::{Hash, Hasher},
+    };
+
+    let inline = FastVec::<u32, 8>::from_array([1, 2, 3]);
+    let mut spilled = FastVec::<u32, 2>::new();
+    spilled.extend([1, 2, 3]);
+
+    // Equality ignores the inline capacity and the storage state
+    assert!(inline == spilled);
+    assert!(inline == [1, 2, 3]);
+    assert!(inline == vec![1, 2, 3]);
+    let slice: &[u32] = &[1, 2, 3];
+    assert!(inline == slice);
+    assert!(inline == *slice);
+    assert!([1, 2, 3] == inline);
+    assert!(vec![1, 2, 3] == inline);
+    assert!(slice == inline);
+    assert!(*slice == inline);
+    assert!(inline != [1, 2]);
+
+    // Cross-type comparison
+    let strings = FastVec::<String, 2>::from_array(["a".to_string()]);
+    assert!(strings == ["a"]);
+
+    // Lexicographic ordering
+    let shorter = FastVec::<u32, 4>::from_array([1, 2]);
+    let larger = FastVec::<u32, 4>::from_array([1, 3]);
+    assert!(shorter < spilled);
+    assert!(spilled < larger);
+    assert_eq!(shorter.cmp(&larger), std::cmp::Ordering::Less);
+    let sorted = [larger.clone(), shorter.clone()]
+        .into_iter()
+        .collect::<BTreeSet<_>>();
+    assert!(sorted.into_iter().next() == Some(shorter));
+
+    // The hash agrees with the slice
+    let hash = |value: &dyn Fn(&mut DefaultHasher)| {
+        let mut hasher = DefaultHasher::new();
+        value(&mut hasher);
+        hasher.finish()
+    };
+    assert_eq!(
+        hash(&|h| spilled.hash(h)),
+        hash(&|h| [1u32, 2, 3][..].hash(h))
+    );
+    assert_eq!(hash(&|h| inline.hash(h)), hash(&|h| spilled.hash(h)));
+
+    let set = [inline].into_iter().collect::<HashSet<_>>();
+    assert!(set.contains(&FastVec::from_array([1, 2, 3])));
+}
+
+#[test]
+fn std_traits() {
+    use std::collections::HashMap;
+
+    #[derive(Debug, Clone, Default)]
+    struct Derived {
+        items: FastVec<u32, 2>,
+    }
+
+    let mut derived = Derived::default();
+    assert!(derived.items.is_empty());
+    derived.items.extend([1, 2, 3]);
+    assert_eq!(format!("{:?}", derived), "Derived { items: [1, 2, 3] }");
+    assert_eq!(derived.clone().items, [1, 2, 3]);
+
+    let buf = FastVec::<u32, 2>::from_array([1, 2]);
+    assert_eq!(buf.as_ref(), &[1, 2]);
+    let mut map = HashMap::new();
+    map.insert(buf, "value");
+    assert_eq!(map.get(&[1, 2][..]), Some(&"value"));
+}
+
+#[test]
+fn clone() {
+    let live = Cell::new(0);
+
+    // A spilled buffer is cloned inline if the items fit
+    let mut buf = FastVec::<String, 2, Doubling, _>::new_in(TrackingAllocator(&live));
+    buf.extend(["a", "b", "c"].map(String::from));
+    buf.pop();
+    let clone = buf.clone();
+    assert!(!clone.is_heap_allocated());
+    assert_eq!(clone, ["a", "b"]);
+    assert_eq!(live.get(), 1);
+
+    // clone_from reuses the heap buffer of the destination
+    let mut dst = FastVec::<String, 2, Doubling, _>::new_in(TrackingAllocator(&live));
+    dst.extend(["x", "y", "z", "w"].map(String::from));
+    let heap = dst.as_ptr();
+    buf.push("c".to_string());
+    dst.clone_from(&buf);
+    assert_eq!(dst, ["a", "b", "c"]);
+    assert_eq!(dst.as_ptr(), heap);
+    assert_eq!(live.get(), 2);
+
+    mem::drop((buf, clone, dst));
+    assert_eq!(live.get(), 0);
+}
+
+#[test]
+fn clone_panic_safety() {
+    // Panics when the item with the marked value is cloned
+    struct PanicOnClone<'a>(u32, &'a Cell<usize>);
+
+    impl<'a> Clone for PanicOnClone<'a> {
+        fn clone(&self) -> Self {
+            if self.0 == 3 {
+                panic!("clone panicked");
+            }
+            self.1.set(self.1.get() + 1);
+            PanicOnClone(self.0, self.1)
+        }
+    }
+
+    impl<'a> Drop for PanicOnClone<'a> {
+        fn drop(&mut self) {
+            self.1.set(self.1.get() - 1);
+        }
+    }
+
+    let live_items = Cell::new(0);
+    let live = Cell::new(0);
+    let mut buf = FastVec::<_, 2, Doubling, _>::new_in(TrackingAllocator(&live));
+    for i in 0..5 {
+        live_items.set(live_items.get() + 1);
+        buf.push(PanicOnClone(i, &live_items));
+    }
+
+    // The items cloned before the panic are dropped with the clone
+    assert!(catch_unwind(AssertUnwindSafe(|| buf.clone())).is_err());
+    assert_eq!(live_items.get(), 5);
+    assert_eq!(live.get(), 1);
+
+    let mut dst = FastVec::<_, 2, Doubling, _>::new_in(TrackingAllocator(&live));
+    assert!(catch_unwind(AssertUnwindSafe(|| dst.clone_from(&buf))).is_err());
+    assert_eq!(dst.len(), 3);
+    assert_eq!(live_items.get(), 8);
+
+    mem::drop((buf, dst));
+    assert_eq!(live_items.get(), 0);
+    assert_eq!(live.get(), 0);
+}
+
+#[test]
+fn conversions() {
+    // A large enough vector is adopted without copying
+    let vec = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+    let heap = vec.as_ptr();
+    let mut buf = FastVec::<String, 2>::from(vec);
+    assert!(buf.is_heap_allocated());
+    assert_eq!(buf, ["a", "b", "c"]);
+    // NOTE: In debug-invariants mode the allocation is resized for the guard
+    // area, which may move it
+    if !cfg!(feature = "debug-invariants") {
+        assert_eq!(buf.as_ptr(), heap);
+    }
+
+    // The adopted heap buffer can grow and is handed back without copying
+    buf.push("d".to_string());
+    let heap = buf.as_ptr();
+    let vec = Vec::from(buf);
+    assert_eq!(vec, ["a", "b", "c", "d"]);
+    if !cfg!(feature = "debug-invariants") {
+        assert_eq!(vec.as_ptr(), heap);
+    }
+
+    // Small vectors are moved inline
+    let buf = FastVec::<String, 8>::from(vec);
+    assert!(!buf.is_heap_allocated());
+    assert_eq!(buf, ["a", "b", "c", "d"]);
+    let vec = Vec::from(buf);
+    assert_eq!(vec, ["a", "b", "c", "d"]);
+
+    let boxed = FastVec::<String, 2>::from(vec).into_boxed_slice();
+    assert_eq!(&*boxed, ["a", "b", "c", "d"]);
+    let boxed = FastVec::<u32, 4>::from([1, 2]).into_boxed_slice();
+    assert_eq!(&*boxed, [1, 2]);
+
+    // Arrays and slices
+    let buf = FastVec::<u32, 2>::from([1, 2, 3]);
+    assert!(buf.is_heap_allocated());
+    assert_eq!(buf, [1, 2, 3]);
+    let buf = FastVec::<u32, 4>::from(&[1, 2, 3][..]);
+    assert!(!buf.is_heap_allocated());
+    assert_eq!(buf, [1, 2, 3]);
+    let buf = FastVec::<u32, 4>::from(&mut [4, 5][..]);
+    assert_eq!(buf, [4, 5]);
+
+    let zst = FastVec::<(), 2>::from(vec![(); 5]);
+    assert_eq!(zst.len(), 5);
+    assert_eq!(Vec::from(zst).len(), 5);
+}
+
+#[test]
+fn into_inner_and_array() {
+    let mut buf = FastVec::<f32, 3>::new();
+    buf.push(1.0);
+    buf.push(2.0);
+    let mut buf = buf.into_inner().unwrap_err();
+    buf.push(3.0);
+    assert_eq!(buf.into_inner().ok(), Some([1.0, 2.0, 3.0]));
+
+    // A spilled buffer only converts into an array of the exact length
+    let live = Cell::new(0);
+    let mut buf = FastVec::<String, 2, Doubling, _>::new_in(TrackingAllocator(&live));
+    buf.extend(["a", "b", "c"].map(String::from));
+    buf.pop();
+    let buf = buf.into_inner().unwrap_err();
+    let buf = buf.into_array::<3>().unwrap_err();
+    assert_eq!(buf.into_array().ok(), Some(["a", "b"].map(String::from)));
+    assert_eq!(live.get(), 0);
+
+    let zst = FastVec::<(), 2>::from_array([(), ()]);
+    assert_eq!(zst.into_inner().ok(), Some([(), ()]));
+}
+
+#[test]
+fn raw_parts() {
+    // Filling the spare capacity directly
+    let mut buf = FastVec::<u8, 4>::new();
+    buf.push(1);
+    let spare = buf.spare_capacity_mut();
+    assert_eq!(spare.len(), 3);
+    spare[0].write(2);
+    spare[1].write(3);
+    unsafe { buf.set_len(3) };
+    assert_eq!(buf, [1, 2, 3]);
+
+    unsafe {
+        buf.reserve(8);
+        buf.as_mut_ptr().add(3).write(4);
+        buf.set_len(4);
+    }
+    assert_eq!(buf, [1, 2, 3, 4]);
+    assert_eq!(buf.as_ptr(), buf[..].as_ptr());
+
+    // Round trip through the raw parts of a spilled buffer
+    let live = Cell::new(0);
+    let buf = FastVec::<String, 2, Doubling, _>::new_in(TrackingAllocator(&live));
+    let mut buf = buf.into_raw_parts().unwrap_err();
+    buf.extend(["a", "b", "c"].map(String::from));
+    let (ptr, len, cap, alloc) = buf.into_raw_parts_with_alloc().ok().unwrap();
+    assert_eq!((len, cap), (3, 4));
+    assert_eq!(live.get(), 1);
+    let buf = unsafe { FastVec::<String, 2, Doubling, _>::from_raw_parts_in(ptr, len, cap, alloc) };
+    assert_eq!(buf, ["a", "b", "c"]);
+    mem::drop(buf);
+    assert_eq!(live.get(), 0);
+
+    // The raw parts are compatible with Vec in every build
+    let mut vec = mem::ManuallyDrop::new(vec![1u32, 2, 3, 4]);
+    let (ptr, len, cap) = (vec.as_mut_ptr(), vec.len(), vec.capacity());
+    let mut buf = unsafe { FastVec::<u32, 2>::from_raw_parts(ptr, len, cap) };
+    buf.push(5);
+    assert_eq!(buf, [1, 2, 3, 4, 5]);
+    let (ptr, len, cap) = buf.into_raw_parts().ok().unwrap();
+    let vec = unsafe { Vec::from_raw_parts(ptr, len, cap) };
+    assert_eq!(vec, [1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn growth_policies() {
+    use super::growth::{FixedIncrement, OneAndHalf, PageAligned};
+
+    let mut buf = FastVec::<u32, 4>::new();
+    buf.extend(0..5);
+    assert_eq!(buf.cap(), 8);
+    buf.extend(5..9);
+    assert_eq!(buf.cap(), 16);
+
+    let mut buf = FastVec::<u32, 4, OneAndHalf>::new();
+    buf.extend(0..5);
+    assert_eq!(buf.cap(), 6);
+    buf.extend(5..7);
+    assert_eq!(buf.cap(), 9);
+
+    let mut buf = FastVec::<u32, 2, FixedIncrement<3>>::new();
+    buf.extend(0..3);
+    assert_eq!(buf.cap(), 5);
+    buf.extend(3..6);
+    assert_eq!(buf.cap(), 8);
+
+    // The policy never allocates less than what's required
+    buf.reserve(100);
+    assert_eq!(buf.cap(), 106);
+
+    let mut buf = FastVec::<u32, 4, PageAligned>::new();
+    buf.extend(0..5);
+    assert_eq!(buf.cap(), 1024);
+    buf.extend(5..1025);
+    assert_eq!(buf.cap(), 2048);
+
+    let mut buf = FastVec::<[u8; 3], 4, PageAligned<OneAndHalf, 64>>::new();
+    buf.extend([[0; 3]; 5]);
+    assert_eq!(buf.cap(), 21);
+}
+
+// Forwards to the global allocator and counts the live allocations
+#[derive(Clone, Copy)]
+struct TrackingAllocator<'a>(&'a Cell<isize>);
+
+unsafe impl<'a> Allocator for TrackingAllocator<'a> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        self.0.set(self.0.get() + 1);
+        Global.allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.0.set(self.0.get() - 1);
+        Global.deallocate(ptr, layout)
+    }
+}
+
+// Refuses every allocation
+struct FailingAllocator;
+
+unsafe impl Allocator for FailingAllocator {
+    fn allocate(&self, _: Layout) -> Result<NonNull<u8>, AllocError> {
+        Err(AllocError)
+    }
+
+    unsafe fn deallocate(&self, _: NonNull<u8>, _: Layout) {
+        unreachable!();
+    }
+}
+
+#[test]
+fn custom_allocator() {
+    let live = Cell::new(0);
+
+    let mut buf = FastVec::<u32, 2, Doubling, _>::new_in(TrackingAllocator(&live));
+    buf.extend(0..2);
+    assert_eq!(live.get(), 0);
+    buf.extend(2..10);
+    assert_eq!(live.get(), 1);
+    buf.shrink_to(3);
+    assert_eq!(live.get(), 1);
+
+    let clone = buf.clone();
+    assert_eq!(&*clone, &*buf);
+    assert_eq!(live.get(), 2);
+    mem::drop(clone);
+    assert_eq!(live.get(), 1);
+
+    buf.truncate(2);
+    buf.shrink_to_fit();
+    assert!(!buf.is_heap_allocated());
+    assert_eq!(live.get(), 0);
+
+    let buf = FastVec::<u32, 2, Doubling, _>::with_capacity_in(8, TrackingAllocator(&live));
+    assert_eq!(live.get(), 1);
+    mem::drop(buf);
+    assert_eq!(live.get(), 0);
+}
+
+#[test]
+fn failing_allocator() {
+    let mut buf = FastVec::<u32, 2, Doubling, _>::new_in(FailingAllocator);
+    buf.try_push(1).unwrap();
+    buf.try_push(2).unwrap();
+    let (value, err) = buf.try_push(3).unwrap_err();
+    assert_eq!(value, 3);
+    assert_eq!(err.kind(), ErrorKind::AllocError);
+    assert_eq!(err.operation(), Operation::Push);
+    assert_eq!(err.capacity(), 2);
+    assert_eq!(err.layout(), Some(Layout::array::<u32>(4).unwrap()));
+    assert_eq!(
+        err.to_string(),
+        "Allocation error of 16 bytes (align 4) during push at capacity 2"
+    );
+    assert_eq!(
+        buf.try_reserve(1).unwrap_err().operation(),
+        Operation::Reserve
+    );
+    assert_eq!(
+        buf.try_extend_from_slice(&[3, 4]).unwrap_err().operation(),
+        Operation::Extend
+    );
+    assert_eq!(&*buf, &[1, 2]);
+
+    assert_eq!(
+        FastVec::<u32, 2, Doubling, _>::try_with_capacity_in(3, FailingAllocator).err(),
+        Some(Error::AllocError {
+            layout: Layout::array::<u32>(3).unwrap(),
+            operation: Operation::WithCapacity,
+            capacity: 2
+        })
+    );
+}
+
+#[test]
+fn const_constructors() {
+    static EMPTY: FastVec<u32, 4> = FastVec::new();
+    const TABLE: FastVec<&str, 4> = FastVec::from_array(["a", "b"]);
+    const FULL: FastVec<u8, 3> = FastVec::from_array([1, 2, 3]);
+
+    thread_local! {
+        static LOCAL: std::cell::RefCell<FastVec<u32, 4>> =
+            const { std::cell::RefCell::new(FastVec::new()) };
+    }
+
+    assert!(EMPTY.is_empty());
+    assert_eq!(&*TABLE, &["a", "b"]);
+    assert!(!TABLE.is_heap_allocated());
+    assert_eq!(&*FULL, &[1, 2, 3]);
+
+    // The pre-filled buffer grows like any other
+    let mut buf = TABLE;
+    buf.extend(["c", "d", "e"]);
+    assert_eq!(&*buf, &["a", "b", "c", "d", "e"]);
+    assert!(buf.is_heap_allocated());
+
+    LOCAL.with(|local| local.borrow_mut().push(1));
+    LOCAL.with(|local| assert_eq!(&**local.borrow(), &[1]));
+
+    // Owned items are dropped with the buffer
+    let count = Cell::new(0);
+    drop(FastVec::<_, 4>::from_array([
+        DropCounter(1, &count),
+        DropCounter(2, &count),
+    ]));
+    assert_eq!(count.get(), 2);
+
+    let zst = FastVec::<(), 4>::from_array([(), ()]);
+    assert_eq!(zst.len(), 2);
+}
+
+#[test]
+fn zero_sized_types() {
+    let mut buf = FastVec::<(), 2>::with_capacity(100);
+    assert_eq!(buf.cap(), usize::MAX);
+    for _ in 0..100 {
+        buf.push(());
+    }
+    assert!(!buf.is_heap_allocated());
+    assert_eq!(buf.len(), 100);
+    assert_eq!(buf.cap(), usize::MAX);
+
+    buf.insert(50, ());
+    assert_eq!(buf.remove(0), ());
+    buf.truncate(60);
+    assert_eq!(buf.drain(10..20).count(), 10);
+    buf.retain(|_| true);
+    buf.dedup();
+    assert_eq!(buf.len(), 1);
+    buf.shrink_to_fit();
+    buf.reserve_exact(1000);
+    assert!(!buf.is_heap_allocated());
+
+    assert_eq!(
+        buf.try_reserve(usize::MAX).unwrap_err().kind(),
+        ErrorKind::CapacityOverflow
+    );
+    assert_eq!(buf.into_iter().count(), 1);
+}
+
+#[test]
+fn drop_panic_safety() {
+    // Panics when the item with the marked value is dropped
+    struct PanicOnDrop<'a>(u32, &'a Cell<usize>);
+
+    impl<'a> Drop for PanicOnDrop<'a> {
+        fn drop(&mut self) {
+            self.1.set(self.1.get() + 1);
+            if self.0 == 1 {
+                panic!("drop panicked");
+            }
+        }
+    }
+
+    let dropped = Cell::new(0);
+    let live = Cell::new(0);
+
+    // Inline
+    let mut buf = FastVec::<_, 4, Doubling, _>::new_in(TrackingAllocator(&live));
+    for i in 0..3 {
+        buf.push(PanicOnDrop(i, &dropped));
+    }
+    assert!(catch_unwind(AssertUnwindSafe(|| mem::drop(buf))).is_err());
+    assert_eq!(dropped.get(), 3);
+
+    // Spilled, the rest of the items are dropped and the heap buffer is freed
+    dropped.set(0);
+    let mut buf = FastVec::<_, 4, Doubling, _>::new_in(TrackingAllocator(&live));
+    for i in 0..10 {
+        buf.push(PanicOnDrop(i, &dropped));
+    }
+    assert!(buf.is_heap_allocated());
+    assert_eq!(live.get(), 1);
+    assert!(catch_unwind(AssertUnwindSafe(|| mem::drop(buf))).is_err());
+    assert_eq!(dropped.get(), 10);
+    assert_eq!(live.get(), 0);
+}
+
+#[test]
+fn zero_sized_types_drop() {
+    static DROPTEST: RwLock<usize> = RwLock::new(0);
+
+    struct Test;
+
+    impl Drop for Test {
+        fn drop(&mut self) {
+            if let Ok(mut write_guard) = DROPTEST.write() {
+                *write_guard += 1;
+            }
+        }
+    }
+
+    let mut buf = FastVec::<Test, 2>::new();
+    for _ in 0..10 {
+        buf.push(Test);
+    }
+    mem::drop(buf.drain(2..5));
+    assert!(DROPTEST.read().unwrap().eq(&3));
+
+    let mut iter = buf.into_iter();
+    mem::drop(iter.next());
+    assert!(DROPTEST.read().unwrap().eq(&4));
+    mem::drop(iter);
+    assert!(DROPTEST.read().unwrap().eq(&10));
+}
+
+#[test]
+fn not_unpin_items() {
+    use std::marker::PhantomPinned;
+
+    struct NotUnpin(u32, PhantomPinned);
+
+    let mut buf = FastVec::<NotUnpin, 2>::new();
+    for i in 0..5 {
+        buf.push(NotUnpin(i, PhantomPinned));
+    }
+    buf.insert(0, NotUnpin(10, PhantomPinned));
+    buf.shrink_to_fit();
+    assert_eq!(
+        buf.iter().map(|item| item.0).collect::<Vec<_>>(),
+        vec![10, 0, 1, 2, 3, 4]
+    );
+
+    // Futures are !Unpin, but can be moved until they are pinned
+    let futures = (0..3).map(|i| async move { i }).collect::<FastVec<_, 4>>();
+    let pinned = futures.into_iter().map(Box::pin).collect::<Vec<_>>();
+    assert_eq!(pinned.len(), 3);
+}
+
+#[test]
+fn auto_traits() {
+    use super::{drain::Drain, into_iter::IntoIter, splice::Splice};
+    use std::{
+        panic::{RefUnwindSafe, UnwindSafe},
+        vec,
+    };
+
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+    fn assert_unwind_safe<T: UnwindSafe + RefUnwindSafe>() {}
+
+    assert_send::<FastVec<String, 4>>();
+    assert_sync::<FastVec<String, 4>>();
+    assert_unwind_safe::<FastVec<String, 4>>();
+
+    assert_send::<IntoIter<String, 4>>();
+    assert_sync::<IntoIter<String, 4>>();
+    assert_unwind_safe::<IntoIter<String, 4>>();
+
+    assert_send::<Drain<'static, String, 4>>();
+    assert_sync::<Drain<'static, String, 4>>();
+    assert_send::<Splice<'static, vec::IntoIter<String>, 4>>();
+    assert_sync::<Splice<'static, vec::IntoIter<String>, 4>>();
+
+    // Moving a buffer into another thread
+    let mut buf = (0..10).map(|x| x.to_string()).collect::<FastVec<_, 4>>();
+    let handle = std::thread::spawn(move || {
+        buf.push("10".to_string());
+        buf
+    });
+    let buf = handle.join().unwrap();
+    assert_eq!(buf.len(), 11);
+
+    // Sharing a buffer between threads
+    let buf = (0..3).collect::<FastVec<u32, 4>>();
+    std::thread::scope(|scope| {
+        scope.spawn(|| assert_eq!(buf.iter().sum::<u32>(), 3));
+        scope.spawn(|| assert_eq!(buf.len(), 3));
+    });
+}
+
+// Returns the message of a caught panic
+#[cfg(feature = "debug-invariants")]
+fn panic_message(err: Box<dyn std::any::Any + Send>) -> String {
+    match err.downcast::<String>() {
+        Ok(message) => *message,
+        Err(err) => err
+            .downcast_ref::<&str>()
+            .copied()
+            .unwrap_or_default()
+            .to_string(),
+    }
+}
+
+#[test]
+#[cfg(feature = "debug-invariants")]
+fn debug_invariants_inline() {
+    fn spare_is_poisoned<const N: usize>(buf: &mut FastVec<u32, N>) -> bool {
+        let spare = buf.spare_capacity_mut();
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                spare.as_ptr() as *const u8,
+                spare.len() * mem::size_of::<u32>(),
+            )
+        };
+        bytes.iter().all(|&byte| byte == 0xA5)
+    }
+
+    // The never initialized inline slots are poisoned, even if the buffer was
+    // built by a const constructor
+    let mut buf = FastVec::<u32, 4>::new();
+    assert!(spare_is_poisoned(&mut buf));
+    buf.push(1);
+    assert!(spare_is_poisoned(&mut buf));
+
+    let mut buf = FastVec::<u32, 4>::from_array([1, 2]);
+    assert!(spare_is_poisoned(&mut buf));
+    assert_eq!(buf.spare_capacity_mut().len(), 2);
+
+    let mut buf = FastVec::<u32, 4>::with_capacity(2);
+    assert!(spare_is_poisoned(&mut buf));
+
+    // So are the popped ones and the ones vacated by moving back inline
+    buf.extend([1, 2, 3]);
+    buf.pop();
+    assert!(spare_is_poisoned(&mut buf));
+
+    buf.extend([3, 4, 5]);
+    buf.truncate(1);
+    buf.shrink_to_fit();
+    assert!(!buf.is_heap_allocated());
+    assert!(spare_is_poisoned(&mut buf));
+    assert_eq!(&*buf, &[1]);
+}
+
+#[test]
+#[cfg(feature = "debug-invariants")]
+// NOTE: The out of bounds write is intentional, Miri rightfully rejects it
+#[cfg_attr(miri, ignore)]
+fn debug_invariants() {
+    let mut buf = FastVec::<u32, 2>::new();
+    buf.extend([1, 2, 3]);
+    assert!(buf.is_heap_allocated());
+
+    // The popped and the never initialized slots are poisoned
+    let cap = buf.cap();
+    buf.pop();
+    let spare = unsafe { std::slice::from_raw_parts(buf.as_ptr().add(2) as *const u8, 4) };
+    assert!(spare.iter().all(|&byte| byte == 0xA5));
+    let spare = unsafe {
+        std::slice::from_raw_parts(
+            buf.as_ptr().add(cap - 1) as *const u8,
+            mem::size_of::<u32>(),
+        )
+    };
+    assert!(spare.iter().all(|&byte| byte == 0xA5));
+
+    // Writing past the end of the heap buffer is caught by the next call
+    unsafe {
+        *buf.as_mut_ptr().add(cap) = 0;
+    }
+    assert!(catch_unwind(AssertUnwindSafe(|| buf.push(4))).is_err());
+    mem::forget(buf);
+
+    // So is writing before the start of it
+    let mut buf = FastVec::<u32, 2>::new();
+    buf.extend([1, 2, 3]);
+    unsafe {
+        *buf.as_mut_ptr().sub(1) = 0;
+    }
+    let err = catch_unwind(AssertUnwindSafe(|| buf.push(4))).unwrap_err();
+    assert_eq!(
+        panic_message(err),
+        "Invariant violated: heap buffer underflow detected"
+    );
+    mem::forget(buf);
+}
+
+#[test]
+#[cfg(feature = "debug-invariants")]
+fn debug_invariants_state() {
+    // A length past the inline capacity would read the inline storage as a
+    // heap buffer, the spill state catches it first
+    let mut buf = FastVec::<u32, 2>::from_array([1, 2]);
+    let err = catch_unwind(AssertUnwindSafe(|| unsafe { buf.set_len(3) })).unwrap_err();
+    assert_eq!(
+        panic_message(err),
+        "Invariant violated: the buffer is inline but its capacity field is 3"
+    );
+    mem::forget(buf);
+
+    // A heap length past the capacity
+    let mut buf = FastVec::<u32, 2>::with_capacity(4);
+    let err = catch_unwind(AssertUnwindSafe(|| unsafe { buf.set_len(5) })).unwrap_err();
+    assert_eq!(
+        panic_message(err),
+        "Invariant violated: length 5 exceeds the capacity 4"
+    );
+    unsafe { buf.set_len(0) };
+
+    // The same through the raw parts
+    let mut buf = FastVec::<u32, 2>::from_array([1, 2]);
+    buf.spill();
+    let (ptr, _, cap) = buf.into_raw_parts().ok().unwrap();
+    let mut buf = unsafe { FastVec::<u32, 2>::from_raw_parts(ptr, 2, cap) };
+    assert!(catch_unwind(AssertUnwindSafe(|| unsafe { buf.set_len(cap + 1) })).is_err());
+    unsafe { buf.set_len(2) };
+}
@@ -0,0 +1,145 @@
+use core::{
+    alloc::Layout,
+    cell::{Cell, UnsafeCell},
+    ptr::NonNull,
+};
+
+#[cfg(not(feature = "alloc"))]
+use super::error::{Error, Operation};
+use super::{
+    allocator::{AllocError, Allocator},
+    error::ErrorKind,
+    fastvec::FastVec,
+    growth::Doubling,
+};
+
+#[test]
+fn inline_operations() {
+    let mut buf = FastVec::<u32, 4>::new();
+    buf.push(1);
+    buf.push(3);
+    buf.insert(1, 2);
+    buf.extend_from_slice(&[4]);
+    assert!(buf == [1, 2, 3, 4]);
+    assert!(!buf.is_heap_allocated());
+
+    assert_eq!(buf.remove(0), 1);
+    assert_eq!(buf.pop(), Some(4));
+    buf.retain(|&item| item != 3);
+    assert!(buf == [2]);
+
+    buf.extend([5, 6, 7]);
+    assert_eq!(buf.drain(1..).sum::<u32>(), 18);
+    assert_eq!(buf.into_iter().sum::<u32>(), 2);
+}
+
+#[test]
+fn zero_sized_types() {
+    let mut buf = FastVec::<(), 2>::new();
+    buf.extend((0..100).map(|_| ()));
+    assert_eq!(buf.len(), 100);
+    assert!(!buf.is_heap_allocated());
+}
+
+#[test]
+#[cfg(not(feature = "alloc"))]
+fn spill_overflows_without_alloc() {
+    let mut buf = FastVec::<u32, 2>::from_array([1, 2]);
+
+    let (value, err) = buf.try_push(3).unwrap_err();
+    assert_eq!(value, 3);
+    assert_eq!(
+        err,
+        Error::CapacityOverflow {
+            operation: Operation::Push,
+            capacity: 2,
+        }
+    );
+    assert_eq!(
+        buf.try_reserve(1),
+        Err(Error::CapacityOverflow {
+            operation: Operation::Reserve,
+            capacity: 2,
+        })
+    );
+    assert_eq!(
+        buf.try_spill(),
+        Err(Error::CapacityOverflow {
+            operation: Operation::Spill,
+            capacity: 2,
+        })
+    );
+    assert!(buf == [1, 2]);
+    assert!(!buf.is_heap_allocated());
+
+    assert_eq!(
+        FastVec::<u32, 2>::try_with_capacity(3).err(),
+        Some(Error::CapacityOverflow {
+            operation: Operation::WithCapacity,
+            capacity: 2,
+        })
+    );
+}
+
+#[test]
+#[cfg(not(feature = "alloc"))]
+#[should_panic(expected = "capacity overflow")]
+fn push_overflows_without_alloc() {
+    let mut buf = FastVec::<u32, 2>::from_array([1, 2]);
+    buf.push(3);
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn spill_with_alloc() {
+    let mut buf = FastVec::<u32, 2>::from_array([1, 2]);
+    buf.push(3);
+    assert!(buf.is_heap_allocated());
+    assert!(buf == [1, 2, 3]);
+}
+
+// Hands out the memory of a fixed buffer and never frees it
+struct Arena {
+    memory: UnsafeCell<[u64; 32]>,
+    used: Cell<usize>,
+}
+
+unsafe impl Allocator for Arena {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        assert!(layout.align() <= 8);
+
+        let start = (self.used.get() + 7) & !7;
+        let end = start + layout.size();
+        if end > 32 * 8 {
+            return Err(AllocError);
+        }
+
+        self.used.set(end);
+        unsafe {
+            Ok(NonNull::new_unchecked(
+                (self.memory.get() as *mut u8).add(start),
+            ))
+        }
+    }
+
+    unsafe fn deallocate(&self, _: NonNull<u8>, _: Layout) {}
+}
+
+#[test]
+fn custom_allocator_spills() {
+    let arena = Arena {
+        memory: UnsafeCell::new([0; 32]),
+        used: Cell::new(0),
+    };
+
+    let mut buf = FastVec::<u32, 2, Doubling, _>::new_in(&arena);
+    buf.extend(0..10);
+    assert!(buf.is_heap_allocated());
+    assert_eq!(buf.iter().sum::<u32>(), 45);
+
+    // NOTE: Unlike the global allocator without the alloc feature, a custom
+    // allocator running out of memory is reported as an allocation error
+    let err = buf.try_reserve(1000).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::AllocError);
+    assert_eq!(buf.len(), 10);
+}